@@ -0,0 +1,49 @@
+use std::{
+    env,
+    fs::{read_dir, write},
+    path::{Path, PathBuf},
+};
+
+// Embeds every file under `assets/` into the binary so the default sprites
+// are available no matter which directory the overlay is started from.
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let assets_dir = manifest_dir.join("assets");
+    println!("cargo:rerun-if-changed={}", assets_dir.display());
+
+    let mut files = Vec::new();
+    collect_files(&assets_dir, &mut files);
+    files.sort();
+
+    let mut generated = String::from("pub static EMBEDDED_ASSETS: &[(&str, &[u8])] = &[\n");
+    for file in files {
+        println!("cargo:rerun-if-changed={}", file.display());
+        let relative = file.strip_prefix(&assets_dir).unwrap();
+        let relative = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        generated.push_str(&format!(
+            "    ({:?}, include_bytes!({:?})),\n",
+            relative,
+            file.display().to_string()
+        ));
+    }
+    generated.push_str("];\n");
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    write(out_dir.join("embedded_assets.rs"), generated).unwrap();
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in read_dir(dir).unwrap().flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            println!("cargo:rerun-if-changed={}", path.display());
+            collect_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
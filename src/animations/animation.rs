@@ -1,31 +1,131 @@
-use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
 use std::vec;
 
+use crate::config::Config;
 use crate::penguin::Message;
+use crate::random;
+use clap::ValueEnum;
 use iced::advanced::graphics::geometry::Frame;
 use iced::border::Radius;
 use iced::widget::canvas::{Cache, Geometry, Path};
+use iced::widget::image;
 use iced::widget::{canvas, column};
 use iced::{
-    Color, Element, Length, Point, Radians, Rectangle, Renderer, Subscription, Task, Theme,
+    Color, Element, Length, Point, Radians, Rectangle, Renderer, Subscription, Task, Theme, Vector,
 };
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
+use super::back_forth_animation::back_forth_assets::{BACK_FORTH_CHARACTER, BACK_FORTH_CLIPS};
+use super::balloon_animation::balloon_animation_assets::BALLOON_CHARACTER;
+use super::body::Body;
 use super::copter_animation::copter_animation::{CopterAnimation, CopterAnimationMessage};
+use super::copter_animation::copter_animation_assets::{COPTER_CHARACTER, COPTER_CLIPS};
+use super::event::{Event, EventBus, EventKind};
 use super::{
     back_forth_animation::back_forth_animation::{
         BackAndForthAnimation, BackAndForthAnimationMessage,
     },
     balloon_animation::balloon_animation::{BalloonAnimation, BalloonAnimationMessage},
 };
+use crate::assets::accessory::ACCESSORIES_FOLDER;
+use crate::assets::clip::TICK_MS;
+use crate::assets::manifest::AccessoryLayer;
+use crate::assets::recolor::PALETTES_FOLDER;
+use crate::assets::registry::AssetRegistry;
+use crate::assets::sprite::Sprite;
 pub struct Animation {
     draw_cache: Cache,
-    back_and_forth_animation: Vec<RefCell<BackAndForthAnimation>>,
+    back_and_forth_animation: Vec<BackAndForthAnimation>,
     balloon_animation: Vec<BalloonAnimation>,
     copter_animation: Vec<CopterAnimation>,
-    balloon_landed: Vec<RefCell<bool>>,
     screen_size: (u32, u32),
-    animations_to_be_spawned: i32,
-    bottom_y_pos: i16,
+    // ticks since the animation started, waves spawn on them
+    ticks: u32,
+    waves_spawned: u32,
+    // every animation lands and walks on it, see `ground`
+    ground_y: f32,
+    assets: Rc<AssetRegistry>,
+    config: Config,
+    // physical pixels per logical pixel of the output
+    scale_factor: f32,
+    events: Rc<EventBus>,
+    // the sides another output continues the world on
+    edges: Edges,
+}
+
+// The kinds of penguins, as the control socket and `msg` name them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Walker,
+    Balloon,
+    Copter,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Walker => write!(f, "walker"),
+            Self::Balloon => write!(f, "balloon"),
+            Self::Copter => write!(f, "copter"),
+        }
+    }
+}
+
+// A penguin as the control socket lists it, with its bounds on the canvas
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entity {
+    pub kind: Kind,
+    pub state: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    // the output it walks on, the animation itself does not know it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+// Which edges of the screen lead onto a neighbouring output; penguins turn
+// back at the closed ones
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Edges {
+    pub left: bool,
+    pub right: bool,
+}
+
+impl Edges {
+    pub fn open(&self, side: Side) -> bool {
+        match side {
+            Side::Left => self.left,
+            Side::Right => self.right,
+        }
+    }
+}
+
+// A penguin that went over an open edge, for the output on that side to
+// take in
+pub struct Departure {
+    pub side: Side,
+    pub penguin: Traveller,
+}
+
+// only ever held for the tick it moves on
+#[allow(clippy::large_enum_variant)]
+pub enum Traveller {
+    Walker(BackAndForthAnimation),
+    // with the walker still aboard
+    Balloon(BalloonAnimation, Option<BackAndForthAnimation>),
+    Copter(CopterAnimation),
 }
 
 #[derive(Debug, Clone)]
@@ -37,68 +137,569 @@ pub enum AnimationMessage {
 }
 
 impl Animation {
-    pub fn new(screen_size: (u32, u32)) -> Self {
-        let y_pos = (screen_size.1 as i16) - 60;
+    pub fn new(screen_size: (u32, u32), assets: Rc<AssetRegistry>, config: Config) -> Self {
+        let mut animation = Self {
+            back_and_forth_animation: Vec::new(),
+            balloon_animation: Vec::new(),
+            draw_cache: Default::default(),
+            screen_size,
+            ticks: 0,
+            waves_spawned: 0,
+            copter_animation: Vec::new(),
+            ground_y: ground(screen_size, &config),
+            assets,
+            config,
+            scale_factor: 1.0,
+            events: Rc::default(),
+            edges: Edges::default(),
+        };
+        if animation.config.spawn.waves > 0 {
+            animation.spawn_wave();
+        }
+        animation
+    }
+
+    // Adds the penguins of one wave
+    fn spawn_wave(&mut self) {
+        self.waves_spawned += 1;
+        let spawn = &self.config.spawn;
+        let counts = [
+            (Kind::Walker, spawn.walkers),
+            (Kind::Balloon, spawn.balloons),
+            (Kind::Copter, spawn.copters),
+        ];
+        for (kind, count) in counts {
+            self.spawn(kind, count as usize);
+        }
+    }
+
+    // Adds up to `count` penguins of `kind`, leaving out those beyond the
+    // maximum population; returns how many were added. A balloon and the
+    // walker it brings down share a randomly picked palette swap.
+    pub fn spawn(&mut self, kind: Kind, count: usize) -> usize {
+        let max_population = self.config.spawn.max_population as usize;
+        let room = max_population.saturating_sub(self.population());
+        let count = count.min(room);
+
+        for _ in 0..count {
+            match kind {
+                Kind::Walker => {
+                    let mut walker = self.new_walker(self.assets.palette_swaps().pick());
+                    let max_x = self.screen_size.0 as f32 - walker.sprite_width;
+                    walker.current_pos_x = random::rng().random_range(0.0..max_x.max(1.0));
+                    self.emit(EventKind::Spawned, kind, &walker);
+                    self.back_and_forth_animation.push(walker);
+                }
+                Kind::Balloon => {
+                    let palette_swap = self.assets.palette_swaps().pick();
+                    let mut balloon = BalloonAnimation::new(
+                        self.screen_size,
+                        self.ground_y,
+                        &self.assets,
+                        palette_swap.clone(),
+                        &self.config,
+                        self.events.clone(),
+                    );
+                    self.emit(EventKind::Spawned, kind, &balloon);
+                    balloon.passenger = Some(self.back_and_forth_animation.len());
+                    self.back_and_forth_animation
+                        .push(self.new_walker(palette_swap));
+                    self.balloon_animation.push(balloon);
+                }
+                Kind::Copter => {
+                    let mut copter = CopterAnimation::new(
+                        self.screen_size,
+                        self.ground_y,
+                        &self.assets,
+                        self.assets.palette_swaps().pick(),
+                        &self.config,
+                        self.events.clone(),
+                    );
+                    copter.set_edges(self.edges);
+                    self.emit(EventKind::Spawned, kind, &copter);
+                    self.copter_animation.push(copter);
+                }
+            }
+        }
+        if count > 0 && count == room {
+            self.events.emit(Event {
+                kind: EventKind::PopulationFull,
+                penguin: None,
+            });
+        }
+        count
+    }
+
+    // Sends off the newest `count` penguins of `kind` that are not leaving
+    // yet, or all of them without a count; returns how many. A passenger
+    // still aboard flies off with its balloon.
+    pub fn despawn(&mut self, kind: Kind, count: Option<usize>) -> usize {
+        let count = count.unwrap_or(usize::MAX);
+        let mut left = 0;
+        match kind {
+            Kind::Walker => {
+                for idx in (0..self.back_and_forth_animation.len()).rev() {
+                    if left == count {
+                        break;
+                    }
+                    if self.riding(idx) || self.back_and_forth_animation[idx].leaving() {
+                        continue;
+                    }
+                    self.back_and_forth_animation[idx].leave();
+                    left += 1;
+                }
+            }
+            Kind::Balloon => {
+                for balloon in self.balloon_animation.iter_mut().rev() {
+                    if left == count {
+                        break;
+                    }
+                    if balloon.leaving() {
+                        continue;
+                    }
+                    if let Some(idx) = balloon.passenger {
+                        self.back_and_forth_animation[idx].leave();
+                    }
+                    balloon.leave();
+                    left += 1;
+                }
+            }
+            Kind::Copter => {
+                for copter in self.copter_animation.iter_mut().rev() {
+                    if left == count {
+                        break;
+                    }
+                    if copter.leaving() {
+                        continue;
+                    }
+                    copter.leave();
+                    left += 1;
+                }
+            }
+        }
+        left
+    }
+
+    // Every penguin with what it is doing and where, walkers first
+    pub fn entities(&self) -> Vec<Entity> {
+        let entity = |kind, state: &str, body: &dyn Body| {
+            let bounds = body.bounds();
+            Entity {
+                kind,
+                state: state.to_string(),
+                x: bounds.x,
+                y: bounds.y,
+                width: bounds.width,
+                height: bounds.height,
+                output: None,
+            }
+        };
+        let walkers = self
+            .back_and_forth_animation
+            .iter()
+            .enumerate()
+            .map(|(idx, walker)| {
+                let state = match self.riding(idx) {
+                    true => "riding",
+                    false => walker.state(),
+                };
+                entity(Kind::Walker, state, walker)
+            });
+        let balloons = self
+            .balloon_animation
+            .iter()
+            .map(|balloon| entity(Kind::Balloon, balloon.state(), balloon));
+        let copters = self
+            .copter_animation
+            .iter()
+            .map(|copter| entity(Kind::Copter, copter.state(), copter));
+        walkers.chain(balloons).chain(copters).collect()
+    }
+
+    fn new_walker(&self, palette_swap: Option<String>) -> BackAndForthAnimation {
+        let mut walker = BackAndForthAnimation::new(
+            self.screen_size,
+            self.ground_y,
+            &self.assets,
+            palette_swap,
+            &self.config,
+            self.events.clone(),
+        );
+        walker.set_edges(self.edges);
+        walker
+    }
+
+    fn emit(&self, event: EventKind, kind: Kind, body: &dyn Body) {
+        self.events.emit(Event::penguin(event, kind, body.foot()));
+    }
+
+    // The events since the last call, oldest first
+    pub fn events(&self) -> Vec<Event> {
+        self.events.drain()
+    }
+
+    // Walkers, including those still in their balloon, and copters in flight
+    pub fn population(&self) -> usize {
+        self.back_and_forth_animation.len() + self.copter_animation.len()
+    }
+
+    // Walkers step out of their balloon where its basket touched down
+    fn step_out(&mut self) {
+        for balloon in &mut self.balloon_animation {
+            if !balloon.touched_down() {
+                continue;
+            }
+            let Some(idx) = balloon.passenger.take() else {
+                continue;
+            };
+            let walker = &mut self.back_and_forth_animation[idx];
+            let foot_offset = walker.foot().x - walker.current_pos_x;
+            walker.current_pos_x = balloon.foot().x - foot_offset;
+        }
+    }
+
+    // Drops the walkers that faded out
+    fn remove_gone_walkers(&mut self) {
+        for walker in self.take_walkers(|_, walker| walker.gone()).values() {
+            self.emit(EventKind::Despawned, Kind::Walker, walker);
+        }
+    }
+
+    // Takes the walkers `take` picks out by their index; balloons keep
+    // pointing at their passenger
+    fn take_walkers(
+        &mut self,
+        take: impl Fn(usize, &BackAndForthAnimation) -> bool,
+    ) -> BTreeMap<usize, BackAndForthAnimation> {
+        let mut taken = BTreeMap::new();
+        let mut new_idx = Vec::with_capacity(self.back_and_forth_animation.len());
+        for (idx, walker) in std::mem::take(&mut self.back_and_forth_animation)
+            .into_iter()
+            .enumerate()
+        {
+            match take(idx, &walker) {
+                true => {
+                    new_idx.push(None);
+                    taken.insert(idx, walker);
+                }
+                false => {
+                    new_idx.push(Some(self.back_and_forth_animation.len()));
+                    self.back_and_forth_animation.push(walker);
+                }
+            }
+        }
+        if !taken.is_empty() {
+            for balloon in &mut self.balloon_animation {
+                balloon.passenger = balloon.passenger.and_then(|idx| new_idx[idx]);
+            }
+        }
+        taken
+    }
+
+    // The side `body` went over when it is past the middle of an open edge
+    fn off_edge(&self, body: &dyn Body) -> Option<Side> {
+        let bounds = body.bounds();
+        let middle = bounds.x + bounds.width / 2.0;
+        let side = match middle {
+            middle if middle < 0.0 => Side::Left,
+            middle if middle > self.screen_size.0 as f32 => Side::Right,
+            _ => return None,
+        };
+        self.edges.open(side).then_some(side)
+    }
+
+    // Opens or closes the edges penguins can leave the screen by
+    pub fn set_edges(&mut self, edges: Edges) {
+        self.edges = edges;
+        for walker in &mut self.back_and_forth_animation {
+            walker.set_edges(edges);
+        }
+        for copter in &mut self.copter_animation {
+            copter.set_edges(edges);
+        }
+    }
 
-        let back_and_forth_animation: Vec<RefCell<BackAndForthAnimation>> = (0..1)
-            .map(|_| RefCell::new(BackAndForthAnimation::new(screen_size, y_pos)))
+    // Takes out the penguins that went over an open edge. Balloons take the
+    // walker still aboard along.
+    pub fn departures(&mut self) -> Vec<Departure> {
+        let balloons: Vec<(usize, Side, Option<usize>)> = self
+            .balloon_animation
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, balloon)| Some((idx, self.off_edge(balloon)?, balloon.passenger)))
+            .collect();
+        let walkers: Vec<(usize, Side)> = self
+            .back_and_forth_animation
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !self.riding(*idx))
+            .filter_map(|(idx, walker)| Some((idx, self.off_edge(walker)?)))
             .collect();
+        let copters: Vec<(usize, Side)> = self
+            .copter_animation
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, copter)| Some((idx, self.off_edge(copter)?)))
+            .collect();
+        if balloons.is_empty() && walkers.is_empty() && copters.is_empty() {
+            return Vec::new();
+        }
 
-        let balloon_animation: Vec<BalloonAnimation> =
-            (0..1).map(|_| BalloonAnimation::new(screen_size)).collect();
+        let mut taken = self.take_walkers(|idx, _| {
+            walkers.iter().any(|(walker, _)| *walker == idx)
+                || balloons
+                    .iter()
+                    .any(|(_, _, passenger)| *passenger == Some(idx))
+        });
+        let mut departures = Vec::new();
+        // from the back, so the indices of the rest hold
+        for (idx, side, passenger) in balloons.into_iter().rev() {
+            let balloon = self.balloon_animation.remove(idx);
+            let passenger = passenger.and_then(|idx| taken.remove(&idx));
+            departures.push(Departure {
+                side,
+                penguin: Traveller::Balloon(balloon, passenger),
+            });
+        }
+        for (idx, side) in walkers {
+            if let Some(walker) = taken.remove(&idx) {
+                departures.push(Departure {
+                    side,
+                    penguin: Traveller::Walker(walker),
+                });
+            }
+        }
+        for (idx, side) in copters.into_iter().rev() {
+            departures.push(Departure {
+                side,
+                penguin: Traveller::Copter(self.copter_animation.remove(idx)),
+            });
+        }
+        departures
+    }
 
-        let copter_animation: Vec<CopterAnimation> =
-            (0..1).map(|_| CopterAnimation::new(screen_size)).collect();
+    // Takes in a penguin from another output; `offset` turns positions on
+    // that output into positions on this one. Walkers walk on at the height
+    // of this ground, flights keep their height in the world.
+    pub fn arrive(&mut self, departure: Departure, offset: Vector) {
+        match departure.penguin {
+            Traveller::Walker(mut walker) => {
+                self.enter_walker(&mut walker, offset);
+                self.back_and_forth_animation.push(walker);
+            }
+            Traveller::Balloon(mut balloon, passenger) => {
+                let bounds = balloon.bounds();
+                balloon.enter(
+                    self.on_screen(bounds.position() + offset, bounds.width),
+                    self.ground_y,
+                    self.events.clone(),
+                );
+                if let Some(mut walker) = passenger {
+                    self.enter_walker(&mut walker, offset);
+                    balloon.passenger = Some(self.back_and_forth_animation.len());
+                    self.back_and_forth_animation.push(walker);
+                }
+                self.balloon_animation.push(balloon);
+            }
+            Traveller::Copter(mut copter) => {
+                let bounds = copter.bounds();
+                copter.enter(
+                    self.on_screen(bounds.position() + offset, bounds.width),
+                    self.screen_size,
+                    self.ground_y,
+                    self.edges,
+                    self.events.clone(),
+                );
+                self.copter_animation.push(copter);
+            }
+        }
+        self.draw_cache.clear();
+    }
 
-        Self {
-            back_and_forth_animation,
-            balloon_animation,
-            balloon_landed: (0..7).map(|_| RefCell::new(false)).collect(),
-            draw_cache: Default::default(),
-            screen_size,
-            animations_to_be_spawned: 0,
-            copter_animation,
-            bottom_y_pos: y_pos,
+    // Keeps the middle of an arriving penguin on the screen, so it does not
+    // go straight back when the surfaces leave gaps at their edges
+    fn on_screen(&self, position: Point, width: f32) -> Point {
+        let max_x = self.screen_size.0 as f32 - width / 2.0;
+        Point::new(position.x.clamp(-width / 2.0, max_x.max(0.0)), position.y)
+    }
+
+    fn enter_walker(&self, walker: &mut BackAndForthAnimation, offset: Vector) {
+        let position = Point::new(walker.current_pos_x + offset.x, 0.0);
+        walker.enter(
+            self.on_screen(position, walker.sprite_width).x,
+            self.screen_size,
+            self.ground_y,
+            self.edges,
+            self.events.clone(),
+        );
+    }
+
+    // The walker at `idx` is still aboard a balloon
+    fn riding(&self, idx: usize) -> bool {
+        self.balloon_animation
+            .iter()
+            .any(|balloon| balloon.passenger == Some(idx))
+    }
+
+    // Walkers still riding down in a balloon are not drawn
+    fn walkers(&self) -> impl Iterator<Item = &BackAndForthAnimation> {
+        self.back_and_forth_animation
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !self.riding(*idx))
+            .map(|(_, walker)| walker)
+    }
+
+    // Character folders the animations load their sprites from
+    pub const CHARACTERS: &[&str] = &[BACK_FORTH_CHARACTER, BALLOON_CHARACTER, COPTER_CHARACTER];
+
+    // Loads every character at startup so missing or broken assets are
+    // reported right away instead of when the first penguin spawns
+    pub fn preload_assets(assets: &AssetRegistry) {
+        assets.preload(BACK_FORTH_CHARACTER, BACK_FORTH_CLIPS);
+        assets.preload(BALLOON_CHARACTER, &[]);
+        assets.balloon_variants();
+        assets.preload(COPTER_CHARACTER, COPTER_CLIPS);
+        assets.palette_swaps();
+        assets.accessories();
+    }
+
+    // Hands the reloaded sprites of `folders` to every running penguin
+    pub fn reload_assets(&mut self, folders: &[String]) {
+        // new palettes recolour every character
+        let changed = |character: &str| {
+            folders
+                .iter()
+                .any(|folder| folder == character || folder == PALETTES_FOLDER)
+        };
+
+        if changed(BACK_FORTH_CHARACTER)
+            || folders.iter().any(|folder| folder == ACCESSORIES_FOLDER)
+        {
+            for animation in &mut self.back_and_forth_animation {
+                animation.reload_assets(&self.assets);
+            }
+        }
+        if changed(BALLOON_CHARACTER) {
+            for animation in &mut self.balloon_animation {
+                animation.reload_assets(&self.assets);
+            }
+        }
+        if changed(COPTER_CHARACTER) {
+            for animation in &mut self.copter_animation {
+                animation.reload_assets(&self.assets);
+            }
+        }
+        self.draw_cache.clear();
+    }
+
+    // Applies a reloaded config; every penguin keeps its place and state.
+    // Kinds the config no longer spawns leave: copters and balloons fly
+    // off, walkers fade out once neither walkers nor balloons spawn.
+    pub fn set_config(&mut self, config: Config) {
+        for walker in &mut self.back_and_forth_animation {
+            walker.set_config(&config);
+        }
+        for balloon in &mut self.balloon_animation {
+            balloon.set_config(&config);
+        }
+        for copter in &mut self.copter_animation {
+            copter.set_config(&config);
         }
+
+        if config.spawn.copters == 0 {
+            self.despawn(Kind::Copter, None);
+        }
+        if config.spawn.balloons == 0 {
+            self.despawn(Kind::Balloon, None);
+        }
+        if config.spawn.walkers == 0 && config.spawn.balloons == 0 {
+            self.despawn(Kind::Walker, None);
+        }
+
+        let moved = config.surface.ground_offset != self.config.surface.ground_offset;
+        self.config = config;
+        if moved {
+            self.lay_out(self.screen_size, Vector::new(1.0, 1.0));
+        }
+        self.draw_cache.clear();
+    }
+
+    // Lays the penguins out again on a resized or rotated screen: the ground
+    // and the walking bounds move, penguins outside are moved back in and
+    // carry on with what they were doing
+    pub fn set_screen_size(&mut self, screen_size: (u32, u32)) {
+        if screen_size == self.screen_size {
+            return;
+        }
+        tracing::info!(
+            "Screen resized from {:?} to {:?}",
+            self.screen_size,
+            screen_size
+        );
+        // penguins keep their place in proportion to the screen
+        let stretch = Vector::new(
+            screen_size.0 as f32 / self.screen_size.0.max(1) as f32,
+            screen_size.1 as f32 / self.screen_size.1.max(1) as f32,
+        );
+        self.lay_out(screen_size, stretch);
+    }
+
+    // Puts the ground where it belongs on a screen of `screen_size` and the
+    // penguins on it
+    fn lay_out(&mut self, screen_size: (u32, u32), stretch: Vector) {
+        self.screen_size = screen_size;
+        self.ground_y = ground(screen_size, &self.config);
+        for walker in &mut self.back_and_forth_animation {
+            walker.set_screen(screen_size, self.ground_y, stretch);
+        }
+        for balloon in &mut self.balloon_animation {
+            balloon.set_screen(screen_size, self.ground_y, stretch);
+        }
+        for copter in &mut self.copter_animation {
+            copter.set_screen(screen_size, self.ground_y, stretch);
+        }
+        self.draw_cache.clear();
+    }
+
+    // Vector sprites are rasterised again at the new scale on the next draw
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.scale_factor = scale_factor;
+        self.draw_cache.clear();
     }
 
     pub fn update(&mut self, message: AnimationMessage) -> Task<Message> {
         match message {
             AnimationMessage::Tick => {
                 self.draw_cache.clear();
-                // This is to add a delay
-                // Can't think of anything better
-                // spawn more copter animation penguins
-                if self.animations_to_be_spawned % 200 == 0
-                    && self.animations_to_be_spawned <= 1000
-                    && self.animations_to_be_spawned >= 200
+                self.ticks += 1;
+                let wave_ticks = (self.config.spawn.interval_ms / TICK_MS).max(1);
+                if self.waves_spawned < self.config.spawn.waves
+                    && self.ticks.is_multiple_of(wave_ticks)
                 {
-                    self.balloon_animation
-                        .push(BalloonAnimation::new(self.screen_size));
-                    self.back_and_forth_animation
-                        .push(RefCell::new(BackAndForthAnimation::new(
-                            self.screen_size,
-                            (self.bottom_y_pos) as i16,
-                        )));
-                    self.copter_animation
-                        .push(CopterAnimation::new(self.screen_size));
-                } else if self.animations_to_be_spawned > 1000 {
-                    return Task::none();
+                    self.spawn_wave();
                 }
-                self.animations_to_be_spawned += 1;
+                // flights that left the screen are over
+                let events = &self.events;
+                self.copter_animation
+                    .retain(|copter| keep(events, Kind::Copter, copter, copter.gone()));
+                self.balloon_animation
+                    .retain(|balloon| keep(events, Kind::Balloon, balloon, balloon.gone()));
+                self.remove_gone_walkers();
                 Task::none()
             }
-            AnimationMessage::BackAndForthMessage(msg) => {
-                Task::batch((0..self.back_and_forth_animation.len()).map(|idx| {
-                    self.back_and_forth_animation[idx]
-                        .borrow_mut()
-                        .update(msg.clone())
-                }))
-            }
-            AnimationMessage::BalloonMessage(msg) => Task::batch(
-                (0..self.balloon_animation.len())
-                    .map(|idx| self.balloon_animation[idx].update(msg.clone())),
+            AnimationMessage::BackAndForthMessage(msg) => Task::batch(
+                (0..self.back_and_forth_animation.len())
+                    .map(|idx| self.back_and_forth_animation[idx].update(msg.clone())),
             ),
+            AnimationMessage::BalloonMessage(msg) => {
+                let task = Task::batch(
+                    (0..self.balloon_animation.len())
+                        .map(|idx| self.balloon_animation[idx].update(msg.clone())),
+                );
+                self.step_out();
+                task
+            }
             AnimationMessage::CopterMessage(msg) => Task::batch(
                 (0..self.copter_animation.len())
                     .map(|idx| self.copter_animation[idx].update(msg.clone())),
@@ -107,12 +708,10 @@ impl Animation {
     }
 
     pub fn subscription(&self) -> iced::Subscription<Message> {
-        let back_and_forth_subscription =
-            Subscription::batch((0..self.back_and_forth_animation.len()).map(|idx| {
-                self.back_and_forth_animation[idx]
-                    .borrow_mut()
-                    .subscription()
-            }));
+        let back_and_forth_subscription = Subscription::batch(
+            (0..self.back_and_forth_animation.len())
+                .map(|idx| self.back_and_forth_animation[idx].subscription()),
+        );
         let balloon_animation_subscription = Subscription::batch(
             (0..self.balloon_animation.len()).map(|idx| self.balloon_animation[idx].subscription()),
         );
@@ -134,107 +733,150 @@ impl Animation {
         content.into()
     }
 
-    fn draw_balloon_and_penguin(&self, frame: &mut Frame<Renderer>, idx: usize) {
-        if self.balloon_animation[idx].landed {
-            let back_forth_y_pos = self.back_and_forth_animation[idx].borrow().current_pos_y;
+    // Image of `sprite` for a `width` x `height` rectangle on the canvas
+    fn sprite_handle(&self, sprite: &Sprite, width: f32, height: f32) -> image::Handle {
+        sprite.handle((
+            (width * self.scale_factor).round() as u32,
+            (height * self.scale_factor).round() as u32,
+        ))
+    }
 
-            if *self.balloon_landed[idx].borrow() == false {
-                *self.balloon_landed[idx].borrow_mut() = true;
+    // Whether `point` is on the visible pixels of any penguin on screen
+    fn penguin_at(&self, point: Point) -> Option<(Kind, &dyn Body)> {
+        let walkers = self
+            .walkers()
+            .map(|walker| (Kind::Walker, walker as &dyn Body));
+        let copters = self
+            .copter_animation
+            .iter()
+            .map(|copter| (Kind::Copter, copter as &dyn Body));
+        let balloons = self
+            .balloon_animation
+            .iter()
+            .map(|balloon| (Kind::Balloon, balloon as &dyn Body));
+        walkers
+            .chain(copters)
+            .chain(balloons)
+            .find(|(_, body)| body.contains(point))
+    }
 
-                let mut back_and_forth_pos = self.back_and_forth_animation[idx].borrow_mut();
+    // Visible pixels of every penguin on screen, the parts of the surface
+    // that take clicks
+    pub fn hitboxes(&self) -> Vec<Rectangle> {
+        let walkers = self.walkers().map(|walker| walker as &dyn Body);
+        let copters = self
+            .copter_animation
+            .iter()
+            .map(|copter| copter as &dyn Body);
+        let balloons = self
+            .balloon_animation
+            .iter()
+            .map(|balloon| balloon as &dyn Body);
+        walkers
+            .chain(copters)
+            .chain(balloons)
+            .map(|body| body.hitbox())
+            .collect()
+    }
 
-                let balloon_x_pos = self.balloon_animation[idx].current_pos_x;
+    // Draws the current frame of `body` in its bounds
+    fn draw_body(&self, frame: &mut Frame<Renderer>, body: &dyn Body) {
+        let bounds = body.bounds();
+        let (clip, tick) = body.clip();
+        let image = iced::advanced::image::Image {
+            handle: self.sprite_handle(&clip.frame(tick), bounds.width, bounds.height),
+            filter_method: Default::default(),
+            rotation: Radians(0.0f32),
+            opacity: body.opacity(),
+            snap: false,
+            border_radius: Radius::default(),
+        };
+        frame.draw_image(bounds, image);
+    }
 
-                back_and_forth_pos.current_pos_x = balloon_x_pos;
+    // Draws the accessories of `layer` a walking penguin wears, placed at the
+    // anchors of its current frame
+    fn draw_accessories(
+        &self,
+        frame: &mut Frame<Renderer>,
+        penguin: &BackAndForthAnimation,
+        layer: AccessoryLayer,
+    ) {
+        let bounds = penguin.bounds();
+        let mirrored = penguin.clip().0.mirrored;
+        for accessory in penguin.accessories() {
+            if accessory.layer != layer {
+                continue;
             }
+            let Some(anchor) = penguin.anchor(&accessory.anchor) else {
+                continue;
+            };
 
-            let image_handle = self.back_and_forth_animation[idx]
-                .borrow()
-                .get_current_image_handle();
+            let width = bounds.width * accessory.size.0;
+            let height = bounds.height * accessory.size.1;
+            let pivot_x = match mirrored {
+                true => 1.0 - accessory.pivot.0,
+                false => accessory.pivot.0,
+            };
             let image = iced::advanced::image::Image {
-                handle: image_handle,
+                handle: self.sprite_handle(accessory.sprite(mirrored), width, height),
                 filter_method: Default::default(),
                 rotation: Radians(0.0f32),
-                opacity: 2.0,
+                opacity: penguin.opacity(),
                 snap: false,
                 border_radius: Radius::default(),
             };
 
             frame.draw_image(
                 Rectangle {
-                    x: self.back_and_forth_animation[idx].borrow().current_pos_x,
-                    y: back_forth_y_pos,
-                    width: self.back_and_forth_animation[idx].borrow().sprite_height,
-                    height: self.back_and_forth_animation[idx].borrow().sprite_width,
+                    x: anchor.x - pivot_x * width,
+                    y: anchor.y - accessory.pivot.1 * height,
+                    width,
+                    height,
                 },
                 image,
             );
+        }
+    }
 
-            let balloon_image_handle = self.balloon_animation[idx].balloon_without_penguin.clone();
-            let balloon_image = iced::advanced::image::Image {
-                handle: balloon_image_handle,
-                filter_method: Default::default(),
-                rotation: Radians(0.0f32),
-                opacity: 2.0,
-                snap: false,
-                border_radius: Radius::default(),
-            };
-
-            frame.draw_image(
-                Rectangle {
-                    x: self.balloon_animation[idx].current_pos_x,
-                    y: self.balloon_animation[idx].current_pos_y,
-                    width: self.balloon_animation[idx].sprite_height,
-                    height: self.balloon_animation[idx].sprite_width,
-                },
-                balloon_image,
-            );
-        } else {
-            let copter_image_handle = self.copter_animation[idx].copter_asset.clone();
-            let copter_image = iced::advanced::image::Image {
-                handle: copter_image_handle,
-                filter_method: Default::default(),
-                opacity: 1.0,
-                snap: false,
-                rotation: Radians(0.0f32),
-                border_radius: Radius::default(),
-            };
-            frame.draw_image(
-                Rectangle {
-                    x: self.copter_animation[idx].current_pos_x,
-                    y: self.copter_animation[idx].current_pos_y,
-                    width: self.copter_animation[idx].sprite_height,
-                    height: self.copter_animation[idx].sprite_width,
-                },
-                copter_image,
-            );
-            let balloon_image_handle = self.balloon_animation[idx].balloon_with_penguin.clone();
-            let balloon_image = iced::advanced::image::Image {
-                handle: balloon_image_handle,
-                filter_method: Default::default(),
-                rotation: Radians(0.0f32),
-                opacity: 2.0,
-                snap: false,
-
-                border_radius: Radius::default(),
-            };
-
-            frame.draw_image(
-                Rectangle {
-                    x: self.balloon_animation[idx].current_pos_x,
-                    y: self.balloon_animation[idx].current_pos_y,
-                    width: self.balloon_animation[idx].sprite_height,
-                    height: self.balloon_animation[idx].sprite_width,
-                },
-                balloon_image,
-            );
+    // Copters, then the walkers on the ground, then the balloons above them
+    fn draw_penguins(&self, frame: &mut Frame<Renderer>) {
+        for copter in &self.copter_animation {
+            self.draw_body(frame, copter);
+        }
+        for walker in self.walkers() {
+            self.draw_accessories(frame, walker, AccessoryLayer::Below);
+            self.draw_body(frame, walker);
+            self.draw_accessories(frame, walker, AccessoryLayer::Above);
+        }
+        for balloon in &self.balloon_animation {
+            self.draw_body(frame, balloon);
         }
     }
 }
 
-impl<Message> canvas::Program<Message> for Animation {
+impl canvas::Program<Message> for Animation {
     type State = ();
 
+    // Clicks on a penguin are reported, clicks next to one go through
+    fn update(
+        &self,
+        _state: &mut Self::State,
+        event: &canvas::Event,
+        _bounds: Rectangle,
+        cursor: iced::mouse::Cursor,
+    ) -> Option<canvas::Action<Message>> {
+        let canvas::Event::Mouse(iced::mouse::Event::ButtonPressed(iced::mouse::Button::Left)) =
+            event
+        else {
+            return None;
+        };
+        let position = cursor.position()?;
+        let (kind, body) = self.penguin_at(position)?;
+        self.emit(EventKind::Clicked, kind, body);
+        Some(canvas::Action::capture())
+    }
+
     fn draw(
         &self,
         _state: &Self::State,
@@ -246,11 +888,23 @@ impl<Message> canvas::Program<Message> for Animation {
         let screen = self.draw_cache.draw(renderer, bounds.size(), |frame| {
             let background = Path::rectangle(Point::ORIGIN, bounds.size());
             frame.fill(&background, Color::TRANSPARENT);
-            for i in 0..self.balloon_animation.len() {
-                self.draw_balloon_and_penguin(frame, i);
-            }
+            self.draw_penguins(frame);
         });
 
         vec![screen]
     }
 }
+
+// Keeps a penguin that is not gone, reporting the ones that are
+fn keep(events: &EventBus, kind: Kind, body: &dyn Body, gone: bool) -> bool {
+    if gone {
+        events.emit(Event::penguin(EventKind::Despawned, kind, body.foot()));
+    }
+    !gone
+}
+
+// The line every penguin walks and lands on, `ground_offset` above the
+// bottom of the screen
+fn ground(screen_size: (u32, u32), config: &Config) -> f32 {
+    (screen_size.1 as f32 - config.surface.ground_offset).max(0.0)
+}
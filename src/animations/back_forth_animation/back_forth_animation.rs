@@ -1,9 +1,16 @@
-use crate::animations::animation::AnimationMessage;
-use crate::animations::back_forth_animation::back_forth_assets::get_penguin_image;
+use crate::animations::animation::{AnimationMessage, Edges, Kind};
+use crate::animations::back_forth_animation::back_forth_assets::get_penguin_clip;
+use crate::animations::body::Body;
+use crate::animations::event::{Event, EventBus, EventKind};
+use crate::assets::accessory::Accessory;
+use crate::assets::clip::Clip;
+use crate::assets::registry::AssetRegistry;
+use crate::config::Config;
 use crate::penguin::Message;
-use iced::widget::image;
-use iced::Task;
+use crate::random;
+use iced::{Rectangle, Task, Vector};
 use rand::Rng;
+use std::rc::Rc;
 
 // The animation states of the sprite
 #[allow(unused)]
@@ -24,89 +31,259 @@ pub struct BackAndForthAnimation {
     start_point: f32,
     screen_size: (u32, u32),
     pub current_pos_x: f32, // current x-coord position of the penguin
-    pub current_pos_y: f32, // current y-coord position of the penguin
-    frame_counter: i32, // to play frames -> stays between 0 - 40 (as we have 40 frames for each animation)
+    ground_y: f32,          // the foot of every frame stands on it
+    frame_counter: i32,     // ticks since the current clip started playing
     previous_start_point: f32,
     pub sprite_height: f32,
     pub sprite_width: f32,
     next_start_point: f32,
     animation_speed: f32,
-    right_walking_image_handle: Vec<image::Handle>,
-    right_to_front_image_handle: Vec<image::Handle>,
-    left_walking_image_handle: Vec<image::Handle>,
-    front_to_left_image_handle: Vec<image::Handle>,
-    left_to_front_image_handle: Vec<image::Handle>,
-    front_to_right_image_handle: Vec<image::Handle>,
+    right_walking_clip: Clip,
+    right_to_front_clip: Clip,
+    left_walking_clip: Clip,
+    front_to_left_clip: Clip,
+    left_to_front_clip: Clip,
+    front_to_right_clip: Clip,
     direction: BackAndForthAnimationState, // holds current animation state value
     counter: i32,                          // increments on each tick, to change animation states
     turn_point: i32,                       // switch directions between left-right
     should_go_left: bool,
+    palette_swap: Option<String>,
+    accessories: Vec<Rc<Accessory>>,
+    sprite_scale: f32,
+    // opacity while fading out after `leave`
+    fading: Option<f32>,
+    events: Rc<EventBus>,
+    // walks on over these edges instead of turning back
+    edges: Edges,
 }
 
+// Ticks between the end of a walk cycle and twice the turn point
+const CYCLE_TAIL: i32 = 12;
+
+// Ticks a leaving penguin takes to fade out
+const FADE_TICKS: f32 = 60.0;
+
 #[derive(Debug, Clone)]
 pub enum BackAndForthAnimationMessage {
     Tick,
 }
 
+// Turn points are picked between these, in ticks of the walk cycle
+const MIN_TURN_POINT: i32 = 300;
+
+fn max_turn_point(screen_size_x: u32) -> i32 {
+    (screen_size_x as i32).saturating_sub(50)
+}
+
 fn randomize_turn_point(screen_size_x: u32) -> i32 {
-    let mut rng = rand::thread_rng();
-    let min = 300;
-    let max = (screen_size_x as i32).saturating_sub(50);
+    let min = MIN_TURN_POINT;
+    let max = max_turn_point(screen_size_x);
     if min >= max {
         return min;
     }
-    rng.random_range(min..max)
+    random::rng().random_range(min..max)
 }
 
 fn randomize_start_point(turn_point: i32) -> f32 {
-    let mut rng = rand::rng();
     let min = 5;
     let max = turn_point.saturating_sub(300);
     if min >= max {
         return min as f32;
     }
-    rng.random_range(min..max) as f32
+    random::rng().random_range(min..max) as f32
 }
 
 impl BackAndForthAnimation {
-    pub fn new(screen_size: (u32, u32), y_pos: i16) -> Self {
-        let bottom = y_pos as f32; // place the penguin in bottom
-        let turn_point = randomize_turn_point(screen_size.0 - 100);
+    pub fn new(
+        screen_size: (u32, u32),
+        ground_y: f32,
+        assets: &AssetRegistry,
+        palette_swap: Option<String>,
+        config: &Config,
+        events: Rc<EventBus>,
+    ) -> Self {
+        let swap = palette_swap.as_deref();
+        let turn_point = randomize_turn_point(screen_size.0.saturating_sub(100));
         let start_point = randomize_start_point(turn_point);
+        let right_walking_clip =
+            get_penguin_clip(assets, BackAndForthAnimationState::RightAnimation, swap);
+        let (sprite_width, sprite_height) = right_walking_clip.sprite_size;
 
         Self {
+            sprite_scale: config.sprite_scale,
             start_point,
-            current_pos_y: bottom,
-            sprite_height: 60.0,
-            sprite_width: 60.0,
+            ground_y,
+            sprite_height: sprite_height * config.sprite_scale,
+            sprite_width: sprite_width * config.sprite_scale,
             frame_counter: 0,
             screen_size,
             previous_start_point: start_point,
             next_start_point: start_point,
-            animation_speed: 1.0,
-            right_walking_image_handle: get_penguin_image(
-                BackAndForthAnimationState::RightAnimation,
-            ),
-            right_to_front_image_handle: get_penguin_image(
+            animation_speed: config.speed.walk,
+            right_walking_clip,
+            right_to_front_clip: get_penguin_clip(
+                assets,
                 BackAndForthAnimationState::RightToFront,
+                swap,
+            ),
+            left_walking_clip: get_penguin_clip(
+                assets,
+                BackAndForthAnimationState::LeftAnimation,
+                swap,
+            ),
+            front_to_left_clip: get_penguin_clip(
+                assets,
+                BackAndForthAnimationState::FrontToLeft,
+                swap,
             ),
-            left_walking_image_handle: get_penguin_image(BackAndForthAnimationState::LeftAnimation),
-            front_to_left_image_handle: get_penguin_image(BackAndForthAnimationState::FrontToLeft),
-            left_to_front_image_handle: get_penguin_image(BackAndForthAnimationState::LeftToFront),
-            front_to_right_image_handle: get_penguin_image(
+            left_to_front_clip: get_penguin_clip(
+                assets,
+                BackAndForthAnimationState::LeftToFront,
+                swap,
+            ),
+            front_to_right_clip: get_penguin_clip(
+                assets,
                 BackAndForthAnimationState::FrontToRight,
+                swap,
             ),
             should_go_left: false,
             direction: BackAndForthAnimationState::RightAnimation,
             counter: 0,
             turn_point,
             current_pos_x: 0.0,
+            palette_swap,
+            accessories: assets.accessories().pick(),
+            fading: None,
+            events,
+            edges: Edges::default(),
+        }
+    }
+
+    // Swaps in freshly loaded clips; position and state carry on
+    pub fn reload_assets(&mut self, assets: &AssetRegistry) {
+        let swap = self.palette_swap.as_deref();
+        self.right_walking_clip =
+            get_penguin_clip(assets, BackAndForthAnimationState::RightAnimation, swap);
+        self.right_to_front_clip =
+            get_penguin_clip(assets, BackAndForthAnimationState::RightToFront, swap);
+        self.left_walking_clip =
+            get_penguin_clip(assets, BackAndForthAnimationState::LeftAnimation, swap);
+        self.front_to_left_clip =
+            get_penguin_clip(assets, BackAndForthAnimationState::FrontToLeft, swap);
+        self.left_to_front_clip =
+            get_penguin_clip(assets, BackAndForthAnimationState::LeftToFront, swap);
+        self.front_to_right_clip =
+            get_penguin_clip(assets, BackAndForthAnimationState::FrontToRight, swap);
+        self.resize();
+
+        // keeps wearing the accessories the pack still has
+        let accessories = assets.accessories();
+        self.accessories = self
+            .accessories
+            .iter()
+            .filter_map(|accessory| accessories.get(&accessory.name))
+            .collect();
+    }
+
+    fn resize(&mut self) {
+        let (sprite_width, sprite_height) = self.right_walking_clip.sprite_size;
+        self.sprite_width = sprite_width * self.sprite_scale;
+        self.sprite_height = sprite_height * self.sprite_scale;
+    }
+
+    // Takes on the speed and size of a reloaded config mid-walk
+    pub fn set_config(&mut self, config: &Config) {
+        self.animation_speed = config.speed.walk;
+        self.sprite_scale = config.sprite_scale;
+        self.resize();
+    }
+
+    pub fn set_edges(&mut self, edges: Edges) {
+        self.edges = edges;
+    }
+
+    // Stays on a resized screen, in the middle of its walk cycle; `stretch`
+    // is the new size over the old one
+    pub fn set_screen(&mut self, screen_size: (u32, u32), ground_y: f32, stretch: Vector) {
+        self.screen_size = screen_size;
+        self.ground_y = ground_y;
+        let max_x = (screen_size.0 as f32 - self.sprite_width).max(0.0);
+        self.current_pos_x = (self.current_pos_x * stretch.x).clamp(0.0, max_x);
+        self.start_point = (self.start_point * stretch.x).min(max_x);
+        // a turn already begun carries on where it was
+        let turn_point = ((self.turn_point as f32 * stretch.x).round() as i32)
+            .min(max_turn_point(screen_size.0))
+            .max(MIN_TURN_POINT);
+        if self.counter >= self.right_turn_start() {
+            self.counter += turn_point - self.turn_point;
+        }
+        self.turn_point = turn_point;
+    }
+
+    // Walks on at `x` on the screen of another output, starting a new walk
+    // cycle in the direction it was going
+    pub fn enter(
+        &mut self,
+        x: f32,
+        screen_size: (u32, u32),
+        ground_y: f32,
+        edges: Edges,
+        events: Rc<EventBus>,
+    ) {
+        self.current_pos_x = x;
+        self.screen_size = screen_size;
+        self.ground_y = ground_y;
+        self.edges = edges;
+        self.events = events;
+        self.turn_point = randomize_turn_point(screen_size.0.saturating_sub(100));
+        self.start_point = randomize_start_point(self.turn_point);
+        self.should_go_left = false;
+        match self.direction {
+            BackAndForthAnimationState::LeftAnimation => self.counter = self.turn_point,
+            _ => {
+                self.direction = BackAndForthAnimationState::RightAnimation;
+                self.counter = 0;
+            }
+        }
+        self.update_frame_counter();
+    }
+
+    // Fades out while walking on
+    pub fn leave(&mut self) {
+        self.fading.get_or_insert(1.0);
+    }
+
+    pub fn leaving(&self) -> bool {
+        self.fading.is_some()
+    }
+
+    pub fn gone(&self) -> bool {
+        self.fading.is_some_and(|opacity| opacity <= 0.0)
+    }
+
+    // What the penguin is doing, as the control socket reports it
+    pub fn state(&self) -> &'static str {
+        if self.leaving() {
+            return "leaving";
+        }
+        match self.direction {
+            BackAndForthAnimationState::RightAnimation => "walk_right",
+            BackAndForthAnimationState::RightToFront => "right_to_front",
+            BackAndForthAnimationState::FrontToLeft => "front_to_left",
+            BackAndForthAnimationState::LeftAnimation => "walk_left",
+            BackAndForthAnimationState::LeftToFront => "left_to_front",
+            BackAndForthAnimationState::FrontToRight => "front_to_right",
+            BackAndForthAnimationState::FrontTalking => "front_talking",
         }
     }
 
     pub fn update(&mut self, message: BackAndForthAnimationMessage) -> Task<Message> {
         match message {
             BackAndForthAnimationMessage::Tick => {
+                if let Some(opacity) = &mut self.fading {
+                    *opacity -= 1.0 / FADE_TICKS;
+                }
                 self.update_animation_state();
                 self.update_position();
                 self.update_frame_counter();
@@ -139,41 +316,77 @@ impl BackAndForthAnimation {
         }
     }
 
+    // The counter timeline of one walk cycle, derived from the clip lengths:
+    //   [0, right_turn_start)                      walk right
+    //   [right_turn_start, front_to_left_start)    turn from right to front
+    //   [front_to_left_start, turn_point)          turn from front to left
+    //   [turn_point, left_walk_end)                walk left
+    //   [left_walk_end, front_to_right_start)      turn from left to front
+    //   [front_to_right_start, cycle_end)          turn from front to right
+    fn right_turn_start(&self) -> i32 {
+        self.front_to_left_start() - self.right_to_front_clip.ticks()
+    }
+
+    fn front_to_left_start(&self) -> i32 {
+        self.turn_point - self.front_to_left_clip.ticks()
+    }
+
+    fn left_walk_end(&self) -> i32 {
+        self.cycle_end() - self.front_to_right_clip.ticks() - self.left_to_front_clip.ticks()
+    }
+
+    fn front_to_right_start(&self) -> i32 {
+        self.left_walk_end() + self.left_to_front_clip.ticks()
+    }
+
+    fn cycle_end(&self) -> i32 {
+        2 * self.turn_point - CYCLE_TAIL
+    }
+
     fn is_transitioning_to_front_from_left(&self) -> bool {
-        self.counter >= (2 * self.turn_point - 60) && self.counter <= (2 * self.turn_point - 36)
+        self.counter >= self.left_walk_end() && self.counter < self.front_to_right_start()
     }
 
     fn is_transitioning_to_right_from_front(&self) -> bool {
-        self.counter > (2 * self.turn_point - 36) && self.counter <= (2 * self.turn_point - 12)
+        self.counter >= self.front_to_right_start() && self.counter < self.cycle_end()
     }
 
     fn is_transitioning_to_front_from_right(&self) -> bool {
-        self.counter >= self.turn_point - 48 && self.counter < self.turn_point - 24
+        self.counter >= self.right_turn_start() && self.counter < self.front_to_left_start()
     }
 
     fn is_transitioning_to_left_from_front(&self) -> bool {
-        self.counter >= self.turn_point - 24 && self.counter < self.turn_point
+        self.counter >= self.front_to_left_start() && self.counter < self.turn_point
     }
 
     fn is_in_left_animation_phase(&self) -> bool {
-        self.counter >= self.turn_point && self.counter < (2 * self.turn_point - 60)
+        self.counter >= self.turn_point && self.counter < self.left_walk_end()
     }
 
     // whenever animation is going from front-right, reset
     fn should_reset_animation(&self) -> bool {
         self.direction == BackAndForthAnimationState::FrontToRight
-            && self.counter > (2 * self.turn_point - 12)
+            && self.counter >= self.cycle_end()
     }
 
     fn reset_animation(&mut self) {
         self.counter = 0;
         self.previous_start_point = self.start_point;
         self.next_start_point = randomize_start_point(self.turn_point);
+        // now and then off to the output on the left
+        if self.edges.left && random::rng().random_ratio(1, 3) {
+            self.next_start_point = -self.sprite_width;
+        }
         self.start_point = self.next_start_point;
         self.turn_point = randomize_turn_point(self.screen_size.0);
         self.direction = BackAndForthAnimationState::RightAnimation;
         self.should_go_left = self.previous_start_point > self.next_start_point;
         self.frame_counter = 0;
+        self.events.emit(Event::penguin(
+            EventKind::TurnedAround,
+            Kind::Walker,
+            self.foot(),
+        ));
     }
 
     fn update_position(&mut self) {
@@ -198,11 +411,13 @@ impl BackAndForthAnimation {
 
     fn update_right_animation_position(&mut self) {
         if self.should_go_left && self.counter >= 30 {
-            self.counter = self.turn_point - 48;
+            self.counter = self.right_turn_start();
             self.should_go_left = false;
         } else {
-            if self.current_pos_x >= (self.screen_size.0 as f32 - self.sprite_width - 1.0) {
-                self.counter = self.turn_point - 24;
+            if !self.edges.right
+                && self.current_pos_x >= (self.screen_size.0 as f32 - self.sprite_width - 1.0)
+            {
+                self.counter = self.front_to_left_start();
                 self.direction = BackAndForthAnimationState::FrontToLeft;
             } else {
                 self.current_pos_x += self.animation_speed;
@@ -213,10 +428,10 @@ impl BackAndForthAnimation {
 
     fn update_left_animation_position(&mut self) {
         if self.current_pos_x <= self.start_point {
-            self.counter = 2 * self.turn_point - 60;
+            self.counter = self.left_walk_end();
         }
-        if self.current_pos_x <= 1.0 {
-            self.counter = 2 * self.turn_point - 36;
+        if !self.edges.left && self.current_pos_x <= 1.0 {
+            self.counter = self.front_to_right_start();
             self.direction = BackAndForthAnimationState::FrontToRight;
         } else {
             self.current_pos_x -= self.animation_speed;
@@ -225,48 +440,48 @@ impl BackAndForthAnimation {
     }
 
     fn update_frame_counter(&mut self) {
-        let total_frames = 40;
-
-        // the frame counter should always be between 0 - 40 (no overflow)
+        // ticks since the clip of the current state started
         self.frame_counter = match self.direction {
-            BackAndForthAnimationState::RightToFront => {
-                let fc = ((self.counter - (self.turn_point - 48)) * total_frames) / 24;
-                fc.min(39)
-            }
-            BackAndForthAnimationState::FrontToLeft => {
-                let fc = ((self.counter - (self.turn_point - 24)) * total_frames) / 24;
-                fc.min(39)
-            }
-            BackAndForthAnimationState::LeftToFront => {
-                let fc = ((self.counter - (2 * self.turn_point - 60)) * total_frames) / 24;
-                fc.min(39)
-            }
-            BackAndForthAnimationState::FrontToRight => {
-                let fc = ((self.counter - (2 * self.turn_point - 36)) * total_frames) / 24;
-                fc.min(39)
-            }
-            _ => self.counter % total_frames,
+            BackAndForthAnimationState::RightToFront => self.counter - self.right_turn_start(),
+            BackAndForthAnimationState::FrontToLeft => self.counter - self.front_to_left_start(),
+            BackAndForthAnimationState::LeftToFront => self.counter - self.left_walk_end(),
+            BackAndForthAnimationState::FrontToRight => self.counter - self.front_to_right_start(),
+            _ => self.counter,
         };
     }
 
-    pub fn get_current_image_handle(&self) -> image::Handle {
+    fn current_clip(&self) -> &Clip {
         match self.direction {
-            BackAndForthAnimationState::LeftAnimation => {
-                self.left_walking_image_handle[self.frame_counter as usize].clone()
-            }
-            BackAndForthAnimationState::RightToFront => {
-                self.right_to_front_image_handle[self.frame_counter as usize].clone()
-            }
-            BackAndForthAnimationState::FrontToLeft => {
-                self.front_to_left_image_handle[self.frame_counter as usize].clone()
-            }
-            BackAndForthAnimationState::LeftToFront => {
-                self.left_to_front_image_handle[self.frame_counter as usize].clone()
-            }
-            BackAndForthAnimationState::FrontToRight => {
-                self.front_to_right_image_handle[self.frame_counter as usize].clone()
-            }
-            _ => self.right_walking_image_handle[self.frame_counter as usize].clone(),
+            BackAndForthAnimationState::LeftAnimation => &self.left_walking_clip,
+            BackAndForthAnimationState::RightToFront => &self.right_to_front_clip,
+            BackAndForthAnimationState::FrontToLeft => &self.front_to_left_clip,
+            BackAndForthAnimationState::LeftToFront => &self.left_to_front_clip,
+            BackAndForthAnimationState::FrontToRight => &self.front_to_right_clip,
+            _ => &self.right_walking_clip,
+        }
+    }
+
+    pub fn accessories(&self) -> &[Rc<Accessory>] {
+        &self.accessories
+    }
+}
+
+impl Body for BackAndForthAnimation {
+    fn bounds(&self) -> Rectangle {
+        let (_, foot_y) = self.current_clip().foot(self.frame_counter);
+        Rectangle {
+            x: self.current_pos_x,
+            y: self.ground_y - foot_y * self.sprite_height,
+            width: self.sprite_width,
+            height: self.sprite_height,
         }
     }
+
+    fn clip(&self) -> (&Clip, i32) {
+        (self.current_clip(), self.frame_counter)
+    }
+
+    fn opacity(&self) -> f32 {
+        self.fading.unwrap_or(1.0).max(0.0)
+    }
 }
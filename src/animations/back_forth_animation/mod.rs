@@ -1,2 +1,3 @@
+#[allow(clippy::module_inception)]
 pub mod back_forth_animation;
 pub mod back_forth_assets;
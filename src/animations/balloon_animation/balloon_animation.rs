@@ -1,25 +1,40 @@
-use super::balloon_animation_assets::get_balloon_image;
-use crate::{animations::animation::AnimationMessage, penguin::Message};
-use iced::{widget::image, Task};
+use super::balloon_animation_assets::{placeholder_balloon, BalloonVariant};
+use crate::{
+    animations::{
+        animation::{AnimationMessage, Kind},
+        body::Body,
+        event::{Event, EventBus, EventKind},
+    },
+    assets::{clip::Clip, registry::AssetRegistry},
+    config::Config,
+    penguin::Message,
+    random,
+};
+use iced::{Point, Rectangle, Task, Vector};
 use rand::Rng;
+use std::rc::Rc;
 
 pub struct BalloonAnimation {
     pub current_pos_x: f32,
     pub current_pos_y: f32,
-    pub balloon_with_penguin: image::Handle,
-    pub balloon_without_penguin: image::Handle,
+    balloon_with_penguin: Clip,
+    balloon_without_penguin: Clip,
+    variant: String,
     pub sprite_height: f32,
     pub sprite_width: f32,
     descent_speed: f32,
+    ascent_speed: f32,
     with_penguin: bool,
     landing_y: f32,
     pub landed: bool,
     hover_offset: f32,
-}
-
-pub enum BalloonAnimationState {
-    BallonWithPenguin,
-    BalloonWithoutPenguin,
+    palette_swap: Option<String>,
+    sprite_scale: f32,
+    ground_y: f32,
+    // walker riding along until the balloon lands, an index into the
+    // walkers of `Animation`
+    pub passenger: Option<usize>,
+    events: Rc<EventBus>,
 }
 
 #[derive(Debug, Clone)]
@@ -28,13 +43,20 @@ pub enum BalloonAnimationMessage {
 }
 
 impl BalloonAnimation {
-    pub fn new(screen_size: (u32, u32)) -> Self {
-        let balloon_with_penguin = get_balloon_image(BalloonAnimationState::BallonWithPenguin);
-        let balloon_without_penguin =
-            get_balloon_image(BalloonAnimationState::BalloonWithoutPenguin);
+    pub fn new(
+        screen_size: (u32, u32),
+        ground_y: f32,
+        assets: &AssetRegistry,
+        palette_swap: Option<String>,
+        config: &Config,
+        events: Rc<EventBus>,
+    ) -> Self {
+        // every spawn picks one of the balloon colours at random
+        let variant = pick_variant(assets, None, palette_swap.as_deref());
 
-        let sprite_height = 180.0;
-        let sprite_width = 180.0;
+        let (sprite_width, sprite_height) = variant.with_penguin.sprite_size;
+        let sprite_width = sprite_width * config.sprite_scale;
+        let sprite_height = sprite_height * config.sprite_scale;
 
         let screen_x = screen_size.0;
 
@@ -42,27 +64,93 @@ impl BalloonAnimation {
 
         let right_margin = screen_x as f32 - (sprite_width * 1.75);
 
-        let mut rnd = rand::rng();
-
-        let random_x = rnd.random_range(left_margin..right_margin);
+        let random_x = match left_margin < right_margin {
+            true => random::rng().random_range(left_margin..right_margin),
+            false => (screen_x as f32 - sprite_width) / 2.0,
+        };
 
-        let landing_y = screen_size.1 as f32 - 200.0;
+        // the basket touches down on the ground
+        let (_, foot_y) = variant.with_penguin.foot(0);
+        let landing_y = ground_y - foot_y * sprite_height;
 
         Self {
             current_pos_x: random_x,
             current_pos_y: 10.0,
-            balloon_with_penguin,
-            balloon_without_penguin,
+            balloon_with_penguin: variant.with_penguin,
+            balloon_without_penguin: variant.without_penguin,
+            variant: variant.name,
             sprite_height,
             sprite_width,
-            descent_speed: 0.5,
+            descent_speed: 0.5 * config.speed.balloon,
+            ascent_speed: 0.7 * config.speed.balloon,
             with_penguin: true,
             landing_y,
             landed: false,
             hover_offset: 0.0,
+            palette_swap,
+            sprite_scale: config.sprite_scale,
+            ground_y,
+            passenger: None,
+            events,
         }
     }
 
+    // Swaps in the freshly loaded sprites of the same colour when the pack
+    // still has it; the flight carries on
+    pub fn reload_assets(&mut self, assets: &AssetRegistry) {
+        let variant = pick_variant(assets, Some(&self.variant), self.palette_swap.as_deref());
+        self.balloon_with_penguin = variant.with_penguin;
+        self.balloon_without_penguin = variant.without_penguin;
+        self.resize();
+        self.variant = variant.name;
+    }
+
+    // Sprite size and the landing height that goes with it
+    fn resize(&mut self) {
+        let (sprite_width, sprite_height) = self.balloon_with_penguin.sprite_size;
+        self.sprite_width = sprite_width * self.sprite_scale;
+        self.sprite_height = sprite_height * self.sprite_scale;
+        let (_, foot_y) = self.balloon_with_penguin.foot(0);
+        self.landing_y = self.ground_y - foot_y * self.sprite_height;
+    }
+
+    // Stays on a resized screen and lands on its new ground; `stretch` is
+    // the new size over the old one
+    pub fn set_screen(&mut self, screen_size: (u32, u32), ground_y: f32, stretch: Vector) {
+        let max_x = (screen_size.0 as f32 - self.sprite_width).max(0.0);
+        self.current_pos_x = (self.current_pos_x * stretch.x).clamp(0.0, max_x);
+        self.current_pos_y *= stretch.y;
+        self.ground_y = ground_y;
+        self.resize();
+    }
+
+    // Flies on at `position` on the screen of another output
+    pub fn enter(&mut self, position: Point, ground_y: f32, events: Rc<EventBus>) {
+        self.current_pos_x = position.x;
+        self.current_pos_y = position.y;
+        self.ground_y = ground_y;
+        self.events = events;
+        self.resize();
+    }
+
+    // Takes on the speed and size of a reloaded config mid-flight
+    pub fn set_config(&mut self, config: &Config) {
+        self.descent_speed = 0.5 * config.speed.balloon;
+        self.ascent_speed = 0.7 * config.speed.balloon;
+        self.sprite_scale = config.sprite_scale;
+        self.resize();
+    }
+
+    // Flies back up, with the penguin still aboard when it had not landed
+    pub fn leave(&mut self) {
+        self.landed = true;
+    }
+
+    // Landed on the ground, rather than leaving before that
+    pub fn touched_down(&self) -> bool {
+        self.landed && !self.with_penguin
+    }
+
     pub fn update(&mut self, message: BalloonAnimationMessage) -> Task<Message> {
         match message {
             BalloonAnimationMessage::Tick => {
@@ -79,12 +167,17 @@ impl BalloonAnimation {
                         self.current_pos_y = self.landing_y;
                         self.landed = true;
                         self.with_penguin = false;
+                        self.events.emit(Event::penguin(
+                            EventKind::Landed,
+                            Kind::Balloon,
+                            self.foot(),
+                        ));
                     }
                 } else {
                     self.landed = true;
                     self.hover_offset = 5.0 * (self.current_pos_y * 0.04).cos();
 
-                    self.current_pos_y -= 0.7;
+                    self.current_pos_y -= self.ascent_speed;
 
                     let drift_speed = 0.8;
                     let drift = drift_speed * (self.current_pos_y * 0.015).sin();
@@ -100,6 +193,25 @@ impl BalloonAnimation {
         }
     }
 
+    // Flew off the top of the screen after landing
+    pub fn gone(&self) -> bool {
+        self.landed && self.current_pos_y <= -self.sprite_height
+    }
+
+    // On its way back up, empty or not
+    pub fn leaving(&self) -> bool {
+        self.landed
+    }
+
+    // What the balloon is doing, as the control socket reports it
+    pub fn state(&self) -> &'static str {
+        match (self.landed, self.with_penguin) {
+            (false, _) => "descending",
+            (true, false) => "ascending",
+            (true, true) => "leaving",
+        }
+    }
+
     pub fn subscription(&self) -> iced::Subscription<Message> {
         iced::time::every(std::time::Duration::from_millis(16)).map(|_| {
             Message::PlayAnimationMessage(AnimationMessage::BalloonMessage(
@@ -108,3 +220,46 @@ impl BalloonAnimation {
         })
     }
 }
+
+impl Body for BalloonAnimation {
+    fn bounds(&self) -> Rectangle {
+        Rectangle {
+            x: self.current_pos_x,
+            y: self.current_pos_y,
+            width: self.sprite_width,
+            height: self.sprite_height,
+        }
+    }
+
+    // balloons show the first frame of their clip
+    fn clip(&self) -> (&Clip, i32) {
+        match self.with_penguin {
+            true => (&self.balloon_with_penguin, 0),
+            false => (&self.balloon_without_penguin, 0),
+        }
+    }
+}
+
+// The variant called `name`, otherwise a random one, in the colours of
+// `palette_swap`
+fn pick_variant(
+    assets: &AssetRegistry,
+    name: Option<&str>,
+    palette_swap: Option<&str>,
+) -> BalloonVariant {
+    let variants = assets.balloon_variants();
+    if variants.is_empty() {
+        return placeholder_balloon(assets);
+    }
+
+    let idx = variants
+        .iter()
+        .position(|variant| Some(variant.name.as_str()) == name)
+        .unwrap_or_else(|| random::rng().random_range(0..variants.len()));
+    let variant = variants[idx].clone();
+    BalloonVariant {
+        with_penguin: assets.recolor(variant.with_penguin, palette_swap),
+        without_penguin: assets.recolor(variant.without_penguin, palette_swap),
+        ..variant
+    }
+}
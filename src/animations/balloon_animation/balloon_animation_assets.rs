@@ -1,39 +1,166 @@
-use std::{
-    env,
-    fs::{read, read_dir},
-};
+use crate::assets::clip::Clip;
+use crate::assets::error::AssetError;
+use crate::assets::manifest::MANIFEST_FILE;
+use crate::assets::registry::AssetRegistry;
+use std::collections::BTreeMap;
+use std::path::Path;
 
-use iced::widget::image;
+pub const BALLOON_CHARACTER: &str = "Balloon Animation";
 
-use super::balloon_animation::BalloonAnimationState;
+const WITH_PENGUIN: &str = "with_penguin";
+const WITHOUT_PENGUIN: &str = "without_penguin";
 
-pub fn get_balloon_image(balloon_animation_state: BalloonAnimationState) -> image::Handle {
-    let root = env::current_dir().unwrap();
+// One balloon colour: the balloon carrying a penguin and the empty balloon
+#[derive(Debug, Clone)]
+pub struct BalloonVariant {
+    // "" for the unnamed variant
+    pub name: String,
+    pub with_penguin: Clip,
+    pub without_penguin: Clip,
+}
 
-    let assets_dir = root.join("assets").join("Balloon Animation");
+#[derive(Default)]
+struct RoleSlots {
+    with_penguin: Option<Clip>,
+    without_penguin: Option<Clip>,
+}
 
-    let mut balloon_images = Vec::new();
-    let mut iced_image_handle = Vec::new();
+// Collects every balloon variant of the pack, sorted by name.
+//
+// Roles come from manifest clips named `with_penguin` / `without_penguin`,
+// optionally followed by `_<variant>` (e.g. `with_penguin_red`). When the
+// manifest declares none, they come from image file names instead:
+// "withoutpenguin" or "withpenguin" (any case, separators ignored) marks the
+// role and the rest of the name the variant, e.g. `Red-BalloonWithPenguin.png`.
+// A folder where two sprites claim the same role is rejected entirely.
+pub fn get_balloon_variants(registry: &AssetRegistry) -> (Vec<BalloonVariant>, Vec<AssetError>) {
+    let character = registry.character(BALLOON_CHARACTER);
+    let mut candidates: Vec<(String, bool, Clip)> = character
+        .clip_names()
+        .filter_map(|name| {
+            let (is_with_penguin, variant) = role_from_clip_name(name)?;
+            Some((variant, is_with_penguin, character.clip(name)))
+        })
+        .collect();
+    let mut source = MANIFEST_FILE.to_string();
 
-    for asset in read_dir(assets_dir).unwrap() {
-        let asset_path = asset.unwrap().path();
-        balloon_images.push(asset_path);
+    if candidates.is_empty() {
+        candidates = registry
+            .loose_images(BALLOON_CHARACTER)
+            .iter()
+            .filter_map(|(file, clip)| {
+                let (is_with_penguin, variant) = role_from_file_name(file)?;
+                Some((variant, is_with_penguin, clip.clone()))
+            })
+            .collect();
+        source = String::new();
+    }
+
+    let path = registry.display(BALLOON_CHARACTER, &source);
+    let mut errors = Vec::new();
+    let mut slots: BTreeMap<String, RoleSlots> = BTreeMap::new();
+    for (variant, is_with_penguin, clip) in candidates {
+        let slot = slots.entry(variant.clone()).or_default();
+        let (role, taken) = if is_with_penguin {
+            (WITH_PENGUIN, &mut slot.with_penguin)
+        } else {
+            (WITHOUT_PENGUIN, &mut slot.without_penguin)
+        };
+        if taken.replace(clip).is_some() {
+            errors.push(AssetError::RoleAmbiguous {
+                path: path.clone(),
+                role: role_label(role, &variant),
+            });
+        }
+    }
+    if !errors.is_empty() {
+        return (Vec::new(), errors);
     }
 
-    for image_path in balloon_images {
-        match read(&image_path) {
-            Ok(image_bytes) => {
-                let image_handle = image::Handle::from_bytes(image_bytes);
-                iced_image_handle.push(image_handle);
-            }
-            Err(e) => {
-                eprintln!("Failed to read image {:?}: {}", image_path, e);
-            }
+    let mut variants = Vec::new();
+    for (variant, slot) in slots {
+        match (slot.with_penguin, slot.without_penguin) {
+            (Some(with_penguin), Some(without_penguin)) => variants.push(BalloonVariant {
+                name: variant,
+                with_penguin,
+                without_penguin,
+            }),
+            (with_penguin, _) => errors.push(AssetError::RoleMissing {
+                path: path.clone(),
+                role: role_label(
+                    if with_penguin.is_none() {
+                        WITH_PENGUIN
+                    } else {
+                        WITHOUT_PENGUIN
+                    },
+                    &variant,
+                ),
+            }),
         }
     }
+    if variants.is_empty() && errors.is_empty() {
+        errors.push(AssetError::RoleMissing {
+            path,
+            role: role_label(WITH_PENGUIN, ""),
+        });
+    }
+
+    (variants, errors)
+}
+
+// Balloon used when the pack provides no complete variant
+pub fn placeholder_balloon(registry: &AssetRegistry) -> BalloonVariant {
+    let sprite_size = registry.character(BALLOON_CHARACTER).sprite_size();
+    BalloonVariant {
+        name: String::new(),
+        with_penguin: Clip::placeholder(sprite_size),
+        without_penguin: Clip::placeholder(sprite_size),
+    }
+}
+
+fn role_label(role: &str, variant: &str) -> String {
+    match variant {
+        "" => role.to_string(),
+        _ => format!("{} ({})", role, variant),
+    }
+}
 
-    match balloon_animation_state {
-        BalloonAnimationState::BallonWithPenguin => iced_image_handle[0].clone(),
-        BalloonAnimationState::BalloonWithoutPenguin => iced_image_handle[1].clone(),
+// `with_penguin_red` -> (true, "red"), `without_penguin` -> (false, "")
+fn role_from_clip_name(name: &str) -> Option<(bool, String)> {
+    let (is_with_penguin, rest) = match name.strip_prefix(WITHOUT_PENGUIN) {
+        Some(rest) => (false, rest),
+        None => (true, name.strip_prefix(WITH_PENGUIN)?),
+    };
+    match rest {
+        "" => Some((is_with_penguin, String::new())),
+        _ => Some((is_with_penguin, rest.strip_prefix('_')?.to_string())),
     }
 }
+
+// `pixelated-Red-BalloonWithPenguin.png` -> (true, "pixelated-red-balloon")
+fn role_from_file_name(file: &str) -> Option<(bool, String)> {
+    let stem = Path::new(file)
+        .file_stem()?
+        .to_string_lossy()
+        .to_lowercase();
+    let compact: String = stem.chars().filter(|c| c.is_alphanumeric()).collect();
+
+    let (is_with_penguin, token) = if compact.contains("withoutpenguin") {
+        (false, "without")
+    } else if compact.contains("withpenguin") {
+        (true, "with")
+    } else {
+        return None;
+    };
+
+    // drop the role words, whatever separators sit between them
+    let start = stem.find(token)?;
+    let end = start + token.len() + stem[start + token.len()..].find("penguin")? + "penguin".len();
+    let variant = format!("{}{}", &stem[..start], &stem[end..])
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    Some((is_with_penguin, variant))
+}
@@ -1,2 +1,3 @@
+#[allow(clippy::module_inception)]
 pub mod balloon_animation;
 pub mod balloon_animation_assets;
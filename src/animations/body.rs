@@ -0,0 +1,51 @@
+use crate::assets::clip::Clip;
+use iced::{Point, Rectangle};
+
+// Where an animation is drawn and where the visible pixels of its current
+// frame sit, going by the hitbox, foot and anchors of its clips
+pub trait Body {
+    // Rectangle the current frame is drawn in
+    fn bounds(&self) -> Rectangle;
+
+    // Clip on screen and the ticks since it started
+    fn clip(&self) -> (&Clip, i32);
+
+    fn hitbox(&self) -> Rectangle {
+        let (clip, tick) = self.clip();
+        let (x, y, width, height) = clip.hitbox(tick);
+        let bounds = self.bounds();
+        Rectangle {
+            x: bounds.x + x * bounds.width,
+            y: bounds.y + y * bounds.height,
+            width: width * bounds.width,
+            height: height * bounds.height,
+        }
+    }
+
+    // Point that stands on the ground
+    fn foot(&self) -> Point {
+        let (clip, tick) = self.clip();
+        self.point(clip.foot(tick))
+    }
+
+    fn anchor(&self, anchor: &str) -> Option<Point> {
+        let (clip, tick) = self.clip();
+        Some(self.point(clip.anchor(anchor, tick)?))
+    }
+
+    // Below 1 while fading out
+    fn opacity(&self) -> f32 {
+        1.0
+    }
+
+    // Whether `point` is on the visible pixels, e.g. a click
+    fn contains(&self, point: Point) -> bool {
+        self.hitbox().contains(point)
+    }
+
+    // Screen position of a point given as fractions of the frame
+    fn point(&self, (x, y): (f32, f32)) -> Point {
+        let bounds = self.bounds();
+        Point::new(bounds.x + x * bounds.width, bounds.y + y * bounds.height)
+    }
+}
@@ -1,24 +1,42 @@
-use super::copter_animation_assets::get_penguin_copter_image;
+use super::copter_animation_assets::get_penguin_copter_clip;
+use crate::animations::animation::{Edges, Kind};
+use crate::animations::body::Body;
+use crate::animations::event::{Event, EventBus, EventKind};
+use crate::assets::clip::Clip;
+use crate::assets::registry::AssetRegistry;
+use crate::config::Config;
 use crate::penguin::Message;
-use iced::widget::image;
-use iced::Task;
+use crate::random;
+use iced::{Point, Rectangle, Task, Vector};
 use rand::Rng;
 use std::f32::consts::TAU;
+use std::rc::Rc;
 
 pub struct CopterAnimation {
     pub current_pos_x: f32,
     pub current_pos_y: f32,
-    pub copter_asset: image::Handle,
+    copter_clip: Clip,
     pub sprite_height: f32,
     pub sprite_width: f32,
     landed: bool,
     landing_y: f32,
     velocity_y: f32,
     base_x: f32,
+    // sideways wind, pixels per tick
+    drift_x: f32,
     time: f32,
     hover_offset_y: f32,
     rotation_angle: f32,
     wind_phase: f32,
+    palette_swap: Option<String>,
+    sprite_scale: f32,
+    ground_y: f32,
+    screen_size: (u32, u32),
+    // drifts on over these edges instead of being blown back
+    edges: Edges,
+    // multiplies the pull of gravity and the lift when leaving
+    speed: f32,
+    events: Rc<EventBus>,
 }
 
 #[derive(Debug, Clone)]
@@ -27,47 +45,134 @@ pub enum CopterAnimationMessage {
 }
 
 impl CopterAnimation {
-    pub fn new(screen_size: (u32, u32)) -> Self {
-        let copter_asset = get_penguin_copter_image();
-        let sprite_height = 60.0;
-        let sprite_width = 60.0;
+    pub fn new(
+        screen_size: (u32, u32),
+        ground_y: f32,
+        assets: &AssetRegistry,
+        palette_swap: Option<String>,
+        config: &Config,
+        events: Rc<EventBus>,
+    ) -> Self {
+        let copter_clip = get_penguin_copter_clip(assets, palette_swap.as_deref());
+        let (sprite_width, sprite_height) = copter_clip.sprite_size;
+        let sprite_width = sprite_width * config.sprite_scale;
+        let sprite_height = sprite_height * config.sprite_scale;
         let screen_x = screen_size.0;
         let left_margin = sprite_width * 1.75;
         let right_margin = screen_x as f32 - (sprite_width * 1.75);
-        let mut rnd = rand::rng();
-        let random_x = rnd.random_range(left_margin..right_margin);
-        let landing_y = screen_size.1 as f32 - 70.0;
+        let random_x = match left_margin < right_margin {
+            true => random::rng().random_range(left_margin..right_margin),
+            false => (screen_x as f32 - sprite_width) / 2.0,
+        };
+        let (_, foot_y) = copter_clip.foot(0);
+        let landing_y = ground_y - foot_y * sprite_height;
+        let drift_x = match random::rng().random_bool(0.5) {
+            true => 1.0,
+            false => -1.0,
+        } * random::rng().random_range(0.2..0.6);
 
         Self {
             current_pos_x: random_x,
             current_pos_y: 10.0,
-            copter_asset,
+            copter_clip,
             sprite_width,
             sprite_height,
             landed: false,
             landing_y,
             velocity_y: 0.0,
             base_x: random_x,
+            drift_x,
             time: 0.0,
             hover_offset_y: 0.0,
             rotation_angle: 0.0,
-            wind_phase: rnd.random_range(0.0..TAU),
+            wind_phase: random::rng().random_range(0.0..TAU),
+            palette_swap,
+            sprite_scale: config.sprite_scale,
+            ground_y,
+            screen_size,
+            edges: Edges::default(),
+            speed: config.speed.copter,
+            events,
         }
     }
 
+    // Swaps in the freshly loaded sprite; the flight carries on
+    pub fn reload_assets(&mut self, assets: &AssetRegistry) {
+        self.copter_clip = get_penguin_copter_clip(assets, self.palette_swap.as_deref());
+        self.resize();
+    }
+
+    // Sprite size and the landing height that goes with it
+    fn resize(&mut self) {
+        let (sprite_width, sprite_height) = self.copter_clip.sprite_size;
+        self.sprite_width = sprite_width * self.sprite_scale;
+        self.sprite_height = sprite_height * self.sprite_scale;
+        let (_, foot_y) = self.copter_clip.foot(0);
+        self.landing_y = self.ground_y - foot_y * self.sprite_height;
+    }
+
+    pub fn set_edges(&mut self, edges: Edges) {
+        self.edges = edges;
+    }
+
+    // Stays on a resized screen and lands on its new ground; `stretch` is
+    // the new size over the old one
+    pub fn set_screen(&mut self, screen_size: (u32, u32), ground_y: f32, stretch: Vector) {
+        let max_x = (screen_size.0 as f32 - self.sprite_width).max(0.0);
+        self.current_pos_x = (self.current_pos_x * stretch.x).clamp(0.0, max_x);
+        self.current_pos_y *= stretch.y;
+        self.base_x = self.current_pos_x;
+        self.ground_y = ground_y;
+        self.screen_size = screen_size;
+        self.resize();
+    }
+
+    // Flies on at `position` on the screen of another output
+    pub fn enter(
+        &mut self,
+        position: Point,
+        screen_size: (u32, u32),
+        ground_y: f32,
+        edges: Edges,
+        events: Rc<EventBus>,
+    ) {
+        self.current_pos_x = position.x;
+        self.current_pos_y = position.y;
+        self.base_x = position.x;
+        self.ground_y = ground_y;
+        self.screen_size = screen_size;
+        self.edges = edges;
+        self.events = events;
+        self.resize();
+    }
+
+    // Takes on the speed and size of a reloaded config mid-flight
+    pub fn set_config(&mut self, config: &Config) {
+        self.speed = config.speed.copter;
+        self.sprite_scale = config.sprite_scale;
+        self.resize();
+    }
+
+    // Flies off from wherever it is
+    pub fn leave(&mut self) {
+        self.landed = true;
+        self.time = self.time.max(10.0);
+    }
+
     pub fn update(&mut self, message: CopterAnimationMessage) -> Task<Message> {
         match message {
             CopterAnimationMessage::Tick => {
+                let leaving = self.leaving();
                 self.time += 0.1;
 
                 if !self.landed {
-                    self.velocity_y += 0.08;
-                    self.velocity_y = self.velocity_y.min(3.0);
+                    self.velocity_y += 0.08 * self.speed;
+                    self.velocity_y = self.velocity_y.min(3.0 * self.speed);
                     self.current_pos_y += self.velocity_y;
 
                     let sway_amplitude = 10.0 + (self.time * 0.3).sin() * 5.0;
                     let sway_frequency = 0.05;
-                    self.current_pos_x = self.base_x;
+                    self.drift();
 
                     let bob_amplitude = 2.0;
                     let bob_frequency = 0.15;
@@ -90,6 +195,11 @@ impl CopterAnimation {
                         self.landed = true;
                         self.current_pos_y = self.landing_y;
                         self.velocity_y = 0.0;
+                        self.events.emit(Event::penguin(
+                            EventKind::Landed,
+                            Kind::Copter,
+                            self.foot(),
+                        ));
                     }
                 } else {
                     let hover_amplitude = 3.0;
@@ -99,13 +209,13 @@ impl CopterAnimation {
                     self.current_pos_x = self.base_x;
 
                     if self.time > 10.0 {
-                        self.velocity_y -= 0.15;
-                        self.velocity_y = self.velocity_y.max(-6.0);
+                        self.velocity_y -= 0.15 * self.speed;
+                        self.velocity_y = self.velocity_y.max(-6.0 * self.speed);
                         self.current_pos_y += self.velocity_y;
 
                         let ascent_sway_amplitude = 20.0;
                         let ascent_sway_frequency = 0.07;
-                        self.current_pos_x = self.base_x;
+                        self.drift();
 
                         let ascent_horizontal_velocity =
                             (self.time * ascent_sway_frequency + self.wind_phase + 3.0).cos()
@@ -121,11 +231,48 @@ impl CopterAnimation {
                     }
                 }
 
+                if !leaving && self.leaving() {
+                    self.events.emit(Event::penguin(
+                        EventKind::TookOff,
+                        Kind::Copter,
+                        self.foot(),
+                    ));
+                }
                 Task::none()
             }
         }
     }
 
+    // Goes with the wind while flying; a closed edge blows it back
+    fn drift(&mut self) {
+        self.base_x += self.drift_x * self.speed;
+        let max_x = (self.screen_size.0 as f32 - self.sprite_width).max(0.0);
+        if (self.base_x < 0.0 && !self.edges.left) || (self.base_x > max_x && !self.edges.right) {
+            self.base_x = self.base_x.clamp(0.0, max_x);
+            self.drift_x = -self.drift_x;
+        }
+        self.current_pos_x = self.base_x;
+    }
+
+    // Flew off the top of the screen after landing
+    pub fn gone(&self) -> bool {
+        self.landed && self.current_pos_y <= -self.sprite_height * 2.0
+    }
+
+    // Past hovering and on its way back up
+    pub fn leaving(&self) -> bool {
+        self.landed && self.time > 10.0
+    }
+
+    // What the copter is doing, as the control socket reports it
+    pub fn state(&self) -> &'static str {
+        match (self.landed, self.leaving()) {
+            (false, _) => "descending",
+            (true, false) => "hovering",
+            (true, true) => "ascending",
+        }
+    }
+
     pub fn subscription(&self) -> iced::Subscription<Message> {
         iced::time::every(std::time::Duration::from_millis(16)).map(|_| {
             Message::PlayAnimationMessage(
@@ -136,3 +283,19 @@ impl CopterAnimation {
         })
     }
 }
+
+impl Body for CopterAnimation {
+    fn bounds(&self) -> Rectangle {
+        Rectangle {
+            x: self.current_pos_x,
+            y: self.current_pos_y,
+            width: self.sprite_width,
+            height: self.sprite_height,
+        }
+    }
+
+    // the copter shows the first frame of its clip
+    fn clip(&self) -> (&Clip, i32) {
+        (&self.copter_clip, 0)
+    }
+}
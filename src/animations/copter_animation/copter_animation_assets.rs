@@ -1,12 +1,9 @@
-use iced::widget::image;
-use std::env;
-use std::fs::read;
+use crate::assets::clip::Clip;
+use crate::assets::registry::AssetRegistry;
 
-pub fn get_penguin_copter_image() -> image::Handle {
-    let root = env::current_dir().unwrap();
+pub const COPTER_CHARACTER: &str = "PenguinCopter";
+pub const COPTER_CLIPS: &[&str] = &["copter"];
 
-    let assets_dir = root.join("assets").join("PenguinCopter");
-    let asset_path = assets_dir.join("pixelated_penguin_copter.png");
-    let image_bytes = read(&asset_path).unwrap();
-    image::Handle::from_bytes(image_bytes)
+pub fn get_penguin_copter_clip(registry: &AssetRegistry, palette_swap: Option<&str>) -> Clip {
+    registry.recolor(registry.clip(COPTER_CHARACTER, "copter"), palette_swap)
 }
@@ -1,2 +1,3 @@
+#[allow(clippy::module_inception)]
 pub mod copter_animation;
 pub mod copter_animation_assets;
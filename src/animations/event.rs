@@ -0,0 +1,69 @@
+use super::animation::Kind;
+use iced::Point;
+use std::{cell::RefCell, fmt};
+
+// Things that happen to the penguins, which hooks from the config react to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Spawned,
+    // a balloon or copter touched the ground
+    Landed,
+    // a copter lifted off again
+    TookOff,
+    // a walker finished its walk cycle and faces right again
+    TurnedAround,
+    Clicked,
+    // left the screen or faded out
+    Despawned,
+    // a spawn filled up the maximum population
+    PopulationFull,
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Spawned => write!(f, "spawned"),
+            Self::Landed => write!(f, "landed"),
+            Self::TookOff => write!(f, "took-off"),
+            Self::TurnedAround => write!(f, "turned-around"),
+            Self::Clicked => write!(f, "clicked"),
+            Self::Despawned => write!(f, "despawned"),
+            Self::PopulationFull => write!(f, "population-full"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub kind: EventKind,
+    // the penguin it happened to and where its foot was, unless the event
+    // is about all of them
+    pub penguin: Option<(Kind, Point)>,
+}
+
+impl Event {
+    pub fn penguin(kind: EventKind, penguin: Kind, foot: Point) -> Self {
+        Self {
+            kind,
+            penguin: Some((penguin, foot)),
+        }
+    }
+}
+
+// Collects the events of an animation and its penguins until the app takes
+// them; shared by every penguin of the animation
+#[derive(Debug, Default)]
+pub struct EventBus {
+    queue: RefCell<Vec<Event>>,
+}
+
+impl EventBus {
+    pub fn emit(&self, event: Event) {
+        tracing::debug!("Penguin event {} {:?}", event.kind, event.penguin);
+        self.queue.borrow_mut().push(event);
+    }
+
+    pub fn drain(&self) -> Vec<Event> {
+        self.queue.take()
+    }
+}
@@ -1,4 +1,6 @@
 pub mod animation;
 pub mod back_forth_animation;
 pub mod balloon_animation;
+pub mod body;
 pub mod copter_animation;
+pub mod event;
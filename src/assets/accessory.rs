@@ -0,0 +1,70 @@
+use super::manifest::AccessoryLayer;
+use super::sprite::Sprite;
+use crate::random;
+use rand::Rng;
+use std::rc::Rc;
+
+// Folder next to the character folders that holds the accessories
+pub const ACCESSORIES_FOLDER: &str = "Accessories";
+
+// An image worn at an anchor of the wearer's clips, see `AccessoryManifest`
+#[derive(Debug)]
+pub struct Accessory {
+    pub name: String,
+    pub anchor: String,
+    pub size: (f32, f32),
+    pub pivot: (f32, f32),
+    pub layer: AccessoryLayer,
+    pub chance: f32,
+    pub sprite: Sprite,
+    // flipped copy worn by mirrored clips
+    pub mirrored: Sprite,
+}
+
+impl Accessory {
+    pub fn sprite(&self, mirrored: bool) -> &Sprite {
+        match mirrored {
+            true => &self.mirrored,
+            false => &self.sprite,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Accessories {
+    accessories: Vec<Rc<Accessory>>,
+}
+
+impl Accessories {
+    pub fn new(accessories: Vec<Rc<Accessory>>) -> Self {
+        Self { accessories }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Rc<Accessory>> {
+        self.accessories
+            .iter()
+            .find(|accessory| accessory.name == name)
+            .cloned()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.accessories
+            .iter()
+            .map(|accessory| accessory.name.as_str())
+    }
+
+    // Rolls every accessory's chance on its own. An accessory is skipped when
+    // one already picked takes the same anchor and layer.
+    pub fn pick(&self) -> Vec<Rc<Accessory>> {
+        let mut picked: Vec<Rc<Accessory>> = Vec::new();
+        for accessory in &self.accessories {
+            let taken = picked
+                .iter()
+                .any(|worn| worn.anchor == accessory.anchor && worn.layer == accessory.layer);
+            if !taken && random::rng().random_range(0.0..1.0) < accessory.chance {
+                picked.push(accessory.clone());
+            }
+        }
+        picked
+    }
+}
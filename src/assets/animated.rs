@@ -0,0 +1,97 @@
+use ::image::{
+    codecs::{gif::GifDecoder, png::PngDecoder},
+    AnimationDecoder, Frame, ImageFormat, ImageResult, RgbaImage,
+};
+use std::io::Cursor;
+
+// One fully composited frame of an animated image with its delay in
+// milliseconds (0 when the file does not say)
+pub type AnimatedFrame = (RgbaImage, u32);
+
+// Decodes every frame of an animated GIF or APNG. Any other image, including
+// a PNG without animation chunks, is a single frame.
+pub fn decode_animation(bytes: &[u8]) -> ImageResult<Vec<AnimatedFrame>> {
+    let frames = match ::image::guess_format(bytes)? {
+        ImageFormat::Gif => GifDecoder::new(Cursor::new(bytes))?
+            .into_frames()
+            .collect_frames()?,
+        ImageFormat::Png if PngDecoder::new(Cursor::new(bytes))?.is_apng()? => {
+            PngDecoder::new(Cursor::new(bytes))?
+                .apng()?
+                .into_frames()
+                .collect_frames()?
+        }
+        _ => return Ok(vec![(::image::load_from_memory(bytes)?.to_rgba8(), 0)]),
+    };
+
+    Ok(frames.into_iter().map(delayed).collect())
+}
+
+fn delayed(frame: Frame) -> AnimatedFrame {
+    let (numer, denom) = frame.delay().numer_denom_ms();
+    let delay = numer.checked_div(denom).unwrap_or(0);
+    (frame.into_buffer(), delay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::image::{codecs::gif::GifEncoder, Delay, Rgba};
+
+    fn frames() -> Vec<RgbaImage> {
+        [[255, 0, 0, 255], [0, 0, 255, 255]]
+            .into_iter()
+            .map(|pixel| RgbaImage::from_pixel(2, 2, Rgba(pixel)))
+            .collect()
+    }
+
+    #[test]
+    fn reads_gif_delays() {
+        let mut gif = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut gif);
+            for (frame, delay) in frames().into_iter().zip([40, 120]) {
+                let delay = Delay::from_numer_denom_ms(delay, 1);
+                encoder
+                    .encode_frame(Frame::from_parts(frame, 0, 0, delay))
+                    .unwrap();
+            }
+        }
+
+        let decoded = decode_animation(&gif).unwrap();
+        let delays: Vec<u32> = decoded.iter().map(|(_, delay)| *delay).collect();
+        assert_eq!(delays, [40, 120]);
+        assert_eq!(decoded[1].0.get_pixel(0, 0), &Rgba([0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn reads_apng_delays() {
+        let mut apng = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut apng, 2, 2);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_animated(2, 0).unwrap();
+            let mut writer = encoder.write_header().unwrap();
+            for (frame, delay) in frames().into_iter().zip([30, 90]) {
+                writer.set_frame_delay(delay, 1000).unwrap();
+                writer.write_image_data(frame.as_raw()).unwrap();
+            }
+        }
+
+        let decoded = decode_animation(&apng).unwrap();
+        let delays: Vec<u32> = decoded.iter().map(|(_, delay)| *delay).collect();
+        assert_eq!(delays, [30, 90]);
+        assert_eq!(decoded[0].0.get_pixel(1, 1), &Rgba([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn still_images_are_one_frame() {
+        let mut png = Vec::new();
+        frames()[0]
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        let decoded = decode_animation(&png).unwrap();
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].1, 0);
+    }
+}
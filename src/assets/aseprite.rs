@@ -0,0 +1,202 @@
+use super::manifest::PlayMode;
+use super::sheet::FrameRect;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+// The JSON written by Aseprite's "Export Sprite Sheet" (array or hash
+// layout) next to the sheet image it describes
+#[derive(Debug, Clone)]
+pub struct AsepriteExport {
+    pub frames: Vec<AsepriteFrame>,
+    // Sheet image, relative to the JSON file
+    pub image: String,
+    pub tags: Vec<AsepriteTag>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AsepriteFrame {
+    pub frame: AsepriteRect,
+    #[serde(default)]
+    pub trimmed: bool,
+    // Where the trimmed `frame` sits on the untrimmed sprite
+    pub sprite_source_size: Option<AsepriteRect>,
+    pub source_size: Option<AsepriteSize>,
+    // Milliseconds
+    pub duration: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct AsepriteRect {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct AsepriteSize {
+    pub w: u32,
+    pub h: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AsepriteTag {
+    pub name: String,
+    pub from: usize,
+    pub to: usize,
+    #[serde(default)]
+    pub direction: String,
+    // Number of times the tag plays, as a string; absent means forever
+    pub repeat: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawExport {
+    frames: RawFrames,
+    meta: RawMeta,
+}
+
+// "Array" layout lists the frames, "Hash" layout keys them by file name in
+// frame order
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawFrames {
+    Array(Vec<AsepriteFrame>),
+    Hash(Map<String, Value>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawMeta {
+    image: String,
+    #[serde(default)]
+    frame_tags: Vec<AsepriteTag>,
+}
+
+impl AsepriteExport {
+    pub fn parse(json: &[u8]) -> Result<Self, serde_json::Error> {
+        let raw: RawExport = serde_json::from_slice(json)?;
+        let frames = match raw.frames {
+            RawFrames::Array(frames) => frames,
+            RawFrames::Hash(frames) => frames
+                .into_iter()
+                .map(|(_, frame)| serde_json::from_value(frame))
+                .collect::<Result<_, _>>()?,
+        };
+
+        Ok(Self {
+            frames,
+            image: raw.meta.image,
+            tags: raw.meta.frame_tags,
+        })
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&AsepriteTag> {
+        self.tags.iter().find(|tag| tag.name == name)
+    }
+}
+
+impl AsepriteFrame {
+    pub fn rect(&self) -> FrameRect {
+        FrameRect {
+            x: self.frame.x,
+            y: self.frame.y,
+            w: self.frame.w,
+            h: self.frame.h,
+            duration: self.duration,
+        }
+    }
+
+    // Offset and full size of a trimmed frame, None when it fills the sprite
+    pub fn untrimmed(&self) -> Option<((u32, u32), (u32, u32))> {
+        match (self.trimmed, self.sprite_source_size, self.source_size) {
+            (true, Some(placed), Some(size)) => Some(((placed.x, placed.y), (size.w, size.h))),
+            _ => None,
+        }
+    }
+}
+
+impl AsepriteTag {
+    // Play mode and whether the frames run back to front
+    pub fn playback(&self) -> (PlayMode, bool) {
+        let mode = match (self.direction.as_str(), self.repeat.as_deref()) {
+            ("pingpong" | "pingpong_reverse", _) => PlayMode::PingPong,
+            (_, Some("1")) => PlayMode::Once,
+            _ => PlayMode::Loop,
+        };
+        let reverse = matches!(self.direction.as_str(), "reverse" | "pingpong_reverse");
+        (mode, reverse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARRAY: &str = r#"{
+        "frames": [
+            {"frame": {"x": 0, "y": 0, "w": 2, "h": 2}, "duration": 100},
+            {
+                "frame": {"x": 2, "y": 0, "w": 1, "h": 1},
+                "trimmed": true,
+                "spriteSourceSize": {"x": 1, "y": 0, "w": 1, "h": 1},
+                "sourceSize": {"w": 2, "h": 2},
+                "duration": 50
+            }
+        ],
+        "meta": {
+            "image": "penguin.png",
+            "frameTags": [
+                {"name": "walk", "from": 0, "to": 1, "direction": "forward"},
+                {"name": "wave", "from": 1, "to": 1, "direction": "pingpong_reverse"},
+                {"name": "fall", "from": 0, "to": 0, "direction": "forward", "repeat": "1"}
+            ]
+        }
+    }"#;
+
+    #[test]
+    fn parses_the_array_layout() {
+        let export = AsepriteExport::parse(ARRAY.as_bytes()).unwrap();
+        assert_eq!(export.image, "penguin.png");
+        assert_eq!(export.frames.len(), 2);
+        assert_eq!(export.frames[0].rect().duration, Some(100));
+        assert_eq!(export.frames[1].rect().duration, Some(50));
+        let walk = export.tag("walk").unwrap();
+        assert_eq!((walk.from, walk.to), (0, 1));
+        assert!(export.tag("swim").is_none());
+    }
+
+    #[test]
+    fn parses_the_hash_layout_in_frame_order() {
+        let export = AsepriteExport::parse(
+            br#"{
+                "frames": {
+                    "penguin 0.aseprite": {"frame": {"x": 0, "y": 0, "w": 2, "h": 2}, "duration": 100},
+                    "penguin 1.aseprite": {"frame": {"x": 2, "y": 0, "w": 2, "h": 2}, "duration": 50}
+                },
+                "meta": {"image": "penguin.png"}
+            }"#,
+        )
+        .unwrap();
+        let xs: Vec<u32> = export.frames.iter().map(|frame| frame.frame.x).collect();
+        assert_eq!(xs, [0, 2]);
+        assert!(export.tags.is_empty());
+    }
+
+    #[test]
+    fn trimmed_frames_know_their_place() {
+        let export = AsepriteExport::parse(ARRAY.as_bytes()).unwrap();
+        assert_eq!(export.frames[0].untrimmed(), None);
+        assert_eq!(export.frames[1].untrimmed(), Some(((1, 0), (2, 2))));
+    }
+
+    #[test]
+    fn tags_map_to_play_modes() {
+        let export = AsepriteExport::parse(ARRAY.as_bytes()).unwrap();
+        let playback = |name| export.tag(name).unwrap().playback();
+        assert_eq!(playback("walk"), (PlayMode::Loop, false));
+        assert_eq!(playback("wave"), (PlayMode::PingPong, true));
+        assert_eq!(playback("fall"), (PlayMode::Once, false));
+    }
+}
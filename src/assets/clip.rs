@@ -0,0 +1,225 @@
+use super::manifest::PlayMode;
+use super::sprite::Sprite;
+use iced::widget::image;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::LazyLock;
+
+// Every animation advances once per 16ms tick (approx 60 fps)
+pub const TICK_MS: u32 = 16;
+
+// Where the visible pixels of every frame are, as fractions of the frame
+// from its top left corner. Empty lists fall back to the whole frame.
+#[derive(Debug, Default)]
+pub struct FrameMetadata {
+    pub anchors: HashMap<String, Vec<(f32, f32)>>,
+    // (x, y, width, height)
+    pub hitboxes: Vec<(f32, f32, f32, f32)>,
+    pub feet: Vec<(f32, f32)>,
+}
+
+// Shared frame list handed out to every animation instance
+pub type Frames = Rc<[Sprite]>;
+
+// Magenta and black checkerboard shown in place of clips that failed to load
+static PLACEHOLDER: LazyLock<image::Handle> = LazyLock::new(|| {
+    let size = 32;
+    let pixels = (0..size * size)
+        .flat_map(|idx| {
+            let (x, y) = (idx % size, idx / size);
+            if (x / 8 + y / 8) % 2 == 0 {
+                [255, 0, 255, 255]
+            } else {
+                [0, 0, 0, 255]
+            }
+        })
+        .collect::<Vec<u8>>();
+    image::Handle::from_rgba(size, size, pixels)
+});
+
+// A loaded clip: its frames, how long each one is shown and how it repeats.
+// Cloning is cheap, the frames are shared between every animation instance.
+#[derive(Debug, Clone)]
+pub struct Clip {
+    pub frames: Frames,
+    durations: Rc<[u32]>,
+    pub mode: PlayMode,
+    pub sprite_size: (f32, f32),
+    metadata: Rc<FrameMetadata>,
+    // the frames are flipped copies, accessories flip along
+    pub mirrored: bool,
+}
+
+impl Clip {
+    pub fn new(
+        frames: Vec<Sprite>,
+        durations: Vec<u32>,
+        mode: PlayMode,
+        sprite_size: (f32, f32),
+    ) -> Self {
+        Self {
+            frames: frames.into(),
+            durations: durations.into(),
+            mode,
+            sprite_size,
+            metadata: Rc::default(),
+            mirrored: false,
+        }
+    }
+
+    pub fn with_metadata(self, metadata: FrameMetadata, mirrored: bool) -> Self {
+        Self {
+            metadata: Rc::new(metadata),
+            mirrored,
+            ..self
+        }
+    }
+
+    // Single frame stand-in for a clip that could not be loaded
+    pub fn placeholder(sprite_size: (f32, f32)) -> Self {
+        Self::new(
+            vec![Sprite::Raster(PLACEHOLDER.clone())],
+            vec![TICK_MS],
+            PlayMode::Loop,
+            sprite_size,
+        )
+    }
+
+    // The same clip showing `frames` instead
+    pub fn with_frames(&self, frames: Frames) -> Self {
+        Self {
+            frames,
+            ..self.clone()
+        }
+    }
+
+    // Milliseconds one pass over the frames takes
+    pub fn duration_ms(&self) -> u32 {
+        self.durations.iter().sum::<u32>().max(1)
+    }
+
+    // Ticks one pass over the frames takes
+    pub fn ticks(&self) -> i32 {
+        self.duration_ms().div_ceil(TICK_MS).max(1) as i32
+    }
+
+    // Index of the frame shown `tick` ticks after the clip started
+    pub fn frame_index(&self, tick: i32) -> usize {
+        if self.frames.len() <= 1 {
+            return 0;
+        }
+
+        let total = self.duration_ms();
+        let elapsed = tick.max(0) as u32 * TICK_MS;
+        let mut time = match self.mode {
+            PlayMode::Loop => elapsed % total,
+            PlayMode::Once => elapsed.min(total - 1),
+            PlayMode::PingPong => {
+                let time = elapsed % (2 * total);
+                if time < total {
+                    time
+                } else {
+                    2 * total - 1 - time
+                }
+            }
+        };
+
+        for (idx, duration) in self.durations.iter().enumerate() {
+            if time < *duration {
+                return idx;
+            }
+            time -= duration;
+        }
+        self.frames.len() - 1
+    }
+
+    // Where `anchor` sits on the frame shown `tick` ticks after the clip started
+    pub fn anchor(&self, anchor: &str, tick: i32) -> Option<(f32, f32)> {
+        self.metadata
+            .anchors
+            .get(anchor)?
+            .get(self.frame_index(tick))
+            .copied()
+    }
+
+    // Box around the visible pixels of the frame shown at `tick`
+    pub fn hitbox(&self, tick: i32) -> (f32, f32, f32, f32) {
+        self.metadata
+            .hitboxes
+            .get(self.frame_index(tick))
+            .copied()
+            .unwrap_or((0.0, 0.0, 1.0, 1.0))
+    }
+
+    // Point of the frame shown at `tick` that stands on the ground
+    pub fn foot(&self, tick: i32) -> (f32, f32) {
+        self.metadata
+            .feet
+            .get(self.frame_index(tick))
+            .copied()
+            .unwrap_or((0.5, 1.0))
+    }
+
+    pub fn frame(&self, tick: i32) -> Sprite {
+        self.frames
+            .get(self.frame_index(tick))
+            .cloned()
+            .unwrap_or_else(|| Sprite::Raster(PLACEHOLDER.clone()))
+    }
+}
+
+impl Default for Clip {
+    fn default() -> Self {
+        Self::placeholder((60.0, 60.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Frames shown for 1, 2 and 1 ticks
+    fn clip(mode: PlayMode) -> Clip {
+        let frames = vec![Sprite::Raster(PLACEHOLDER.clone()); 3];
+        Clip::new(
+            frames,
+            vec![TICK_MS, 2 * TICK_MS, TICK_MS],
+            mode,
+            (60.0, 60.0),
+        )
+    }
+
+    fn indices(clip: &Clip, ticks: std::ops::Range<i32>) -> Vec<usize> {
+        ticks.map(|tick| clip.frame_index(tick)).collect()
+    }
+
+    #[test]
+    fn loop_starts_over() {
+        let clip = clip(PlayMode::Loop);
+        assert_eq!(clip.ticks(), 4);
+        assert_eq!(indices(&clip, 0..9), [0, 1, 1, 2, 0, 1, 1, 2, 0]);
+    }
+
+    #[test]
+    fn once_stays_on_the_last_frame() {
+        let clip = clip(PlayMode::Once);
+        assert_eq!(indices(&clip, 0..7), [0, 1, 1, 2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn ping_pong_plays_back_and_forth() {
+        let clip = clip(PlayMode::PingPong);
+        assert_eq!(indices(&clip, 0..9), [0, 1, 1, 2, 2, 1, 1, 0, 0]);
+    }
+
+    #[test]
+    fn ticks_before_the_start_show_the_first_frame() {
+        assert_eq!(clip(PlayMode::Loop).frame_index(-3), 0);
+    }
+
+    #[test]
+    fn single_frames_never_advance() {
+        let clip = Clip::placeholder((60.0, 60.0));
+        assert_eq!(indices(&clip, 0..3), [0, 0, 0]);
+    }
+}
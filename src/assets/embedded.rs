@@ -0,0 +1,2 @@
+// Generated by build.rs: (path relative to `assets/`, file contents)
+include!(concat!(env!("OUT_DIR"), "/embedded_assets.rs"));
@@ -0,0 +1,210 @@
+use std::{fmt, io};
+
+// Everything that can go wrong while loading a character folder. Each variant
+// carries the offending path so problems can be reported at startup.
+#[derive(Debug)]
+pub enum AssetError {
+    ManifestUnreadable {
+        path: String,
+        source: io::Error,
+    },
+    ManifestInvalid {
+        path: String,
+        source: toml::de::Error,
+    },
+    DirectoryUnreadable {
+        path: String,
+        source: io::Error,
+    },
+    FrameUnreadable {
+        path: String,
+        source: io::Error,
+    },
+    FrameUndecodable {
+        path: String,
+        source: ::image::ImageError,
+    },
+    ClipMissing {
+        path: String,
+        clip: String,
+    },
+    ClipEmpty {
+        path: String,
+        clip: String,
+    },
+    SheetInvalid {
+        path: String,
+        reason: String,
+    },
+    RectsInvalid {
+        path: String,
+        source: serde_json::Error,
+    },
+    SvgInvalid {
+        path: String,
+        source: resvg::usvg::Error,
+    },
+    AsepriteInvalid {
+        path: String,
+        source: serde_json::Error,
+    },
+    TagMissing {
+        path: String,
+        tag: String,
+    },
+    MirrorInvalid {
+        path: String,
+        clip: String,
+        reason: String,
+    },
+    // `field` is the hitbox, the foot or an anchor of the clip
+    MetadataInvalid {
+        path: String,
+        clip: String,
+        field: String,
+        reason: String,
+    },
+    AccessoryInvalid {
+        path: String,
+        accessory: String,
+        reason: String,
+    },
+    PaletteInvalid {
+        path: String,
+        reason: String,
+    },
+    SwapInvalid {
+        path: String,
+        swap: String,
+        reason: String,
+    },
+    RoleAmbiguous {
+        path: String,
+        role: String,
+    },
+    RoleMissing {
+        path: String,
+        role: String,
+    },
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::ManifestUnreadable { path, source } => {
+                write!(f, "{}: failed to read manifest: {}", path, source)
+            }
+            AssetError::ManifestInvalid { path, source } => {
+                write!(f, "{}: invalid manifest: {}", path, source)
+            }
+            AssetError::DirectoryUnreadable { path, source } => {
+                write!(f, "{}: failed to read directory: {}", path, source)
+            }
+            AssetError::FrameUnreadable { path, source } => {
+                write!(f, "{}: failed to read image: {}", path, source)
+            }
+            AssetError::FrameUndecodable { path, source } => {
+                write!(f, "{}: failed to decode image: {}", path, source)
+            }
+            AssetError::ClipMissing { path, clip } => {
+                write!(f, "{}: clip {:?} is not declared", path, clip)
+            }
+            AssetError::ClipEmpty { path, clip } => {
+                write!(f, "{}: clip {:?} has no usable frames", path, clip)
+            }
+            AssetError::SheetInvalid { path, reason } => {
+                write!(f, "{}: invalid sprite sheet: {}", path, reason)
+            }
+            AssetError::RectsInvalid { path, source } => {
+                write!(f, "{}: invalid frame rectangles: {}", path, source)
+            }
+            AssetError::SvgInvalid { path, source } => {
+                write!(f, "{}: invalid SVG: {}", path, source)
+            }
+            AssetError::AsepriteInvalid { path, source } => {
+                write!(f, "{}: invalid Aseprite export: {}", path, source)
+            }
+            AssetError::TagMissing { path, tag } => {
+                write!(f, "{}: no frame tag named {:?}", path, tag)
+            }
+            AssetError::MirrorInvalid { path, clip, reason } => {
+                write!(
+                    f,
+                    "{}: clip {:?} cannot be mirrored: {}",
+                    path, clip, reason
+                )
+            }
+            AssetError::MetadataInvalid {
+                path,
+                clip,
+                field,
+                reason,
+            } => {
+                write!(
+                    f,
+                    "{}: {} of clip {:?} is unusable: {}",
+                    path, field, clip, reason
+                )
+            }
+            AssetError::AccessoryInvalid {
+                path,
+                accessory,
+                reason,
+            } => {
+                write!(
+                    f,
+                    "{}: accessory {:?} is unusable: {}",
+                    path, accessory, reason
+                )
+            }
+            AssetError::PaletteInvalid { path, reason } => {
+                write!(f, "{}: invalid palette: {}", path, reason)
+            }
+            AssetError::SwapInvalid { path, swap, reason } => {
+                write!(
+                    f,
+                    "{}: palette swap {:?} is unusable: {}",
+                    path, swap, reason
+                )
+            }
+            AssetError::RoleAmbiguous { path, role } => {
+                write!(f, "{}: more than one sprite claims the {} role", path, role)
+            }
+            AssetError::RoleMissing { path, role } => {
+                write!(f, "{}: no sprite provides the {} role", path, role)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AssetError::ManifestUnreadable { source, .. }
+            | AssetError::DirectoryUnreadable { source, .. }
+            | AssetError::FrameUnreadable { source, .. } => Some(source),
+            AssetError::ManifestInvalid { source, .. } => Some(source),
+            AssetError::FrameUndecodable { source, .. } => Some(source),
+            AssetError::SvgInvalid { source, .. } => Some(source),
+            AssetError::RectsInvalid { source, .. }
+            | AssetError::AsepriteInvalid { source, .. } => Some(source),
+            AssetError::ClipMissing { .. }
+            | AssetError::ClipEmpty { .. }
+            | AssetError::SheetInvalid { .. }
+            | AssetError::MetadataInvalid { .. }
+            | AssetError::AccessoryInvalid { .. }
+            | AssetError::PaletteInvalid { .. }
+            | AssetError::SwapInvalid { .. }
+            | AssetError::TagMissing { .. }
+            | AssetError::MirrorInvalid { .. }
+            | AssetError::RoleAmbiguous { .. }
+            | AssetError::RoleMissing { .. } => None,
+        }
+    }
+}
+
+pub fn report(errors: &[AssetError]) {
+    for error in errors {
+        eprintln!("Asset error: {}", error);
+    }
+}
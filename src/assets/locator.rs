@@ -0,0 +1,142 @@
+use super::embedded::EMBEDDED_ASSETS;
+use std::{
+    borrow::Cow,
+    env,
+    fs::{read, read_dir},
+    io,
+    path::PathBuf,
+};
+
+const APP_DIR: &str = "wayland-penguins";
+
+// Resolves character folders (e.g. "Balloon Animation") against an ordered
+// list of directories. The first directory that contains the folder provides
+// all of its files; when none does, the sprites built into the binary are used.
+#[derive(Debug, Clone)]
+pub struct AssetLocator {
+    search_path: Vec<PathBuf>,
+}
+
+// A character folder, either on disk or built into the binary
+#[derive(Debug, Clone)]
+pub enum AssetDir {
+    Disk(PathBuf),
+    Embedded(String),
+}
+
+impl AssetLocator {
+    // Search order: the explicit `--assets` directory, $XDG_DATA_HOME/wayland-penguins,
+    // then every $XDG_DATA_DIRS entry joined with wayland-penguins.
+    pub fn new(explicit_dir: Option<PathBuf>) -> Self {
+        let mut search_path = Vec::new();
+
+        if let Some(dir) = explicit_dir {
+            search_path.push(dir);
+        }
+
+        if let Some(data_home) = xdg_data_home() {
+            search_path.push(data_home.join(APP_DIR));
+        }
+
+        search_path.extend(xdg_data_dirs().into_iter().map(|dir| dir.join(APP_DIR)));
+
+        Self { search_path }
+    }
+
+    // Only `dir`, then the built-in sprites
+    pub fn pack(dir: PathBuf) -> Self {
+        Self {
+            search_path: vec![dir],
+        }
+    }
+
+    pub fn search_path(&self) -> &[PathBuf] {
+        &self.search_path
+    }
+
+    pub fn open(&self, folder: &str) -> AssetDir {
+        self.search_path
+            .iter()
+            .map(|dir| dir.join(folder))
+            .find(|dir| dir.is_dir())
+            .map(AssetDir::Disk)
+            .unwrap_or_else(|| AssetDir::Embedded(folder.to_string()))
+    }
+}
+
+impl AssetDir {
+    // Human readable location of `relative` inside this folder
+    pub fn display(&self, relative: &str) -> String {
+        match self {
+            AssetDir::Disk(dir) => dir.join(relative).display().to_string(),
+            AssetDir::Embedded(folder) => format!("<built-in>/{}/{}", folder, relative),
+        }
+    }
+
+    pub fn read(&self, relative: &str) -> io::Result<Cow<'static, [u8]>> {
+        match self {
+            AssetDir::Disk(dir) => read(dir.join(relative)).map(Cow::Owned),
+            AssetDir::Embedded(folder) => {
+                let path = format!("{}/{}", folder, relative);
+                EMBEDDED_ASSETS
+                    .iter()
+                    .find(|(embedded_path, _)| *embedded_path == path)
+                    .map(|(_, bytes)| Cow::Borrowed(*bytes))
+                    .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+            }
+        }
+    }
+
+    // Relative paths of the files directly inside `sub_dir` ("" for the folder
+    // itself), sorted by file name
+    pub fn list(&self, sub_dir: &str) -> io::Result<Vec<String>> {
+        let sub_dir = match sub_dir.trim_matches('/') {
+            "." => "",
+            sub_dir => sub_dir,
+        };
+        let relative = |name: &str| match sub_dir {
+            "" => name.to_string(),
+            _ => format!("{}/{}", sub_dir, name),
+        };
+
+        let mut files = match self {
+            AssetDir::Disk(dir) => read_dir(dir.join(sub_dir))?
+                .flatten()
+                .filter(|entry| entry.path().is_file())
+                .map(|entry| relative(&entry.file_name().to_string_lossy()))
+                .collect::<Vec<_>>(),
+            AssetDir::Embedded(folder) => {
+                let prefix = format!("{}/{}", folder, relative(""));
+                let files = EMBEDDED_ASSETS
+                    .iter()
+                    .filter_map(|(path, _)| path.strip_prefix(&prefix))
+                    .filter(|name| !name.contains('/'))
+                    .map(relative)
+                    .collect::<Vec<_>>();
+                if files.is_empty() {
+                    return Err(io::Error::from(io::ErrorKind::NotFound));
+                }
+                files
+            }
+        };
+        files.sort();
+        Ok(files)
+    }
+}
+
+fn xdg_data_home() -> Option<PathBuf> {
+    env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+}
+
+fn xdg_data_dirs() -> Vec<PathBuf> {
+    let dirs = env::var_os("XDG_DATA_DIRS")
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+
+    env::split_paths(&dirs)
+        .filter(|path| path.is_absolute())
+        .collect()
+}
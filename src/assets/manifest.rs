@@ -0,0 +1,339 @@
+use super::sheet::{FrameRect, SheetGrid};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+// File name of the manifest inside every character folder
+pub const MANIFEST_FILE: &str = "manifest.toml";
+
+// Describes the clips of one character, e.g. `assets/Back-Forth Animation/manifest.toml`:
+//
+//   sprite_size = [60.0, 60.0]
+//
+//   [clips.walk_right]
+//   directory = "Right Animation"
+//   frame_count = 40
+//   frame_duration = 16
+//   mode = "loop"
+//
+//   [clips.walk_left]
+//   directory = "Left Animation"
+//   frame_count = 40
+//
+// A character drawn facing one way only can get the other side by flipping
+// a clip; its boxes and points flip along:
+//
+//   [clips.walk_left]
+//   mirror_of = "walk_right"
+//
+// Clips can also come from Aseprite exports, where every tag becomes a clip
+// of the same name, or from animated GIF/APNG files:
+//
+//   aseprite = ["penguin.json"]
+//
+//   [clips.wave]
+//   animation = "wave.gif"
+//
+// Frames in `files` or `directory` may be SVG files; they are rasterised at
+// the size they are drawn, so they stay sharp on scaled outputs.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CharacterManifest {
+    // Default on-screen size (width, height) of every clip
+    pub sprite_size: Option<(f32, f32)>,
+    // Aseprite JSON exports whose tags become clips, unless `clips` declares
+    // one of the same name. An export without tags becomes one clip named
+    // after the file.
+    #[serde(default)]
+    pub aseprite: Vec<String>,
+    #[serde(default)]
+    pub clips: HashMap<String, ClipManifest>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClipManifest {
+    // Folder (relative to the manifest) whose files, in file name order, are the frames
+    pub directory: Option<String>,
+    // Explicit frame files relative to the manifest, used instead of `directory`
+    pub files: Option<Vec<String>>,
+    // Sprite sheet holding every frame, sliced by `grid` or `rects`
+    pub sheet: Option<String>,
+    pub grid: Option<SheetGrid>,
+    pub rects: Option<SheetRects>,
+    // Aseprite JSON export; the frames of `tag` (the clip name by default)
+    // or all frames when the export has no such tag
+    pub aseprite: Option<String>,
+    pub tag: Option<String>,
+    // Animated GIF or APNG whose frames and delays make up the clip
+    pub animation: Option<String>,
+    // Another clip whose frames, flipped horizontally, make up this one.
+    // Timing, mode and size follow that clip unless set here as well.
+    pub mirror_of: Option<String>,
+    // Only the first `frame_count` frames are used
+    pub frame_count: Option<usize>,
+    // Milliseconds every frame is shown when its source gives no duration
+    pub frame_duration: Option<u32>,
+    // Milliseconds per frame, overriding any other duration
+    pub durations: Option<Vec<u32>>,
+    // Defaults to the direction of the Aseprite tag, otherwise loop
+    pub mode: Option<PlayMode>,
+    // Play the frames back to front
+    #[serde(default)]
+    pub reverse: bool,
+    // Flip every frame horizontally
+    #[serde(default)]
+    pub mirror: bool,
+    pub sprite_size: Option<(f32, f32)>,
+    // Named points accessories attach to (head, neck, hand...), as fractions
+    // of the frame files from their top left corner: `head = [0.5, 0.05]`
+    // for every frame or `head = [[0.5, 0.05], [0.51, 0.06], ...]` one per
+    // frame. Mirrored clips flip them along, like the hitbox and foot.
+    #[serde(default)]
+    pub anchors: HashMap<String, FrameValues<(f32, f32)>>,
+    // Box around the visible pixels, `[x, y, width, height]` in the same
+    // fractions; clicks and collisions use it. The whole frame when unset.
+    pub hitbox: Option<FrameValues<(f32, f32, f32, f32)>>,
+    // Point that stands on the ground, `[x, y]`. Bottom centre when unset.
+    pub foot: Option<FrameValues<(f32, f32)>>,
+}
+
+// One value for every frame of a clip, or one value per frame
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum FrameValues<T> {
+    Fixed(T),
+    PerFrame(Vec<T>),
+}
+
+impl<T: Copy> FrameValues<T> {
+    // Value of frame `idx`, which `check` made sure exists
+    pub fn get(&self, idx: usize) -> T {
+        match self {
+            FrameValues::Fixed(value) => *value,
+            FrameValues::PerFrame(values) => values[idx],
+        }
+    }
+
+    pub fn check(&self, frame_count: usize) -> Result<(), String> {
+        match self {
+            FrameValues::PerFrame(values) if values.len() < frame_count => Err(format!(
+                "{} values for {} frames",
+                values.len(),
+                frame_count
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Frame rectangles of a sprite sheet, inline or in a JSON file next to the manifest
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum SheetRects {
+    Inline(Vec<FrameRect>),
+    File(String),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PlayMode {
+    #[default]
+    Loop,
+    Once,
+    PingPong,
+}
+
+// Milliseconds per frame when neither the manifest nor the source say
+pub const DEFAULT_FRAME_DURATION: u32 = 16;
+
+impl CharacterManifest {
+    pub fn parse(source: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(source)
+    }
+}
+
+// File name of the palette swap list inside the `Palettes` folder
+pub const PALETTES_FILE: &str = "palettes.toml";
+
+// Palette swaps every character can be recoloured with, e.g.
+// `assets/Palettes/palettes.toml`:
+//
+//   source = "penguins.gpl"
+//
+//   [swaps.golden]
+//   palette = "golden.hex"
+//   chance = 0.02
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PalettesManifest {
+    // Palette the sprites are drawn with
+    pub source: String,
+    #[serde(default)]
+    pub swaps: BTreeMap<String, SwapManifest>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SwapManifest {
+    // Same number of colours as `source`, in the same order
+    pub palette: String,
+    // Probability (0 to 1) that a spawn gets this swap
+    #[serde(default)]
+    pub chance: f32,
+}
+
+impl PalettesManifest {
+    pub fn parse(source: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(source)
+    }
+}
+
+// File name of the accessory list inside the `Accessories` folder
+pub const ACCESSORIES_FILE: &str = "accessories.toml";
+
+// Images drawn onto penguins at the anchors of their clips, e.g.
+// `assets/Accessories/accessories.toml`:
+//
+//   [accessories.top_hat]
+//   image = "top_hat.svg"
+//   anchor = "head"
+//   size = [0.4, 0.3]
+//   pivot = [0.5, 0.85]
+//   chance = 0.15
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccessoriesManifest {
+    #[serde(default)]
+    pub accessories: BTreeMap<String, AccessoryManifest>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccessoryManifest {
+    pub image: String,
+    // Anchor name of the clips it is drawn at
+    pub anchor: String,
+    // Fraction of the wearer's sprite size
+    pub size: (f32, f32),
+    // Point of the image placed on the anchor, as fractions of the image
+    #[serde(default = "centre")]
+    pub pivot: (f32, f32),
+    #[serde(default)]
+    pub layer: AccessoryLayer,
+    // Probability (0 to 1) that a spawn wears it
+    #[serde(default)]
+    pub chance: f32,
+}
+
+// Whether an accessory is drawn in front of the wearer or behind it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessoryLayer {
+    #[default]
+    Above,
+    Below,
+}
+
+fn centre() -> (f32, f32) {
+    (0.5, 0.5)
+}
+
+impl AccessoriesManifest {
+    pub fn parse(source: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_clips() {
+        let manifest = CharacterManifest::parse(
+            r#"
+            sprite_size = [60.0, 60.0]
+
+            [clips.walk_right]
+            directory = "Right Animation"
+            frame_count = 40
+            frame_duration = 20
+            mode = "ping-pong"
+
+            [clips.walk_left]
+            files = ["left/01.png", "left/02.png"]
+            reverse = true
+            mirror = true
+            sprite_size = [30.0, 30.0]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.sprite_size, Some((60.0, 60.0)));
+        let right = &manifest.clips["walk_right"];
+        assert_eq!(right.directory.as_deref(), Some("Right Animation"));
+        assert_eq!(right.frame_count, Some(40));
+        assert_eq!(right.frame_duration, Some(20));
+        assert_eq!(right.mode, Some(PlayMode::PingPong));
+        assert!(!right.reverse);
+        let left = &manifest.clips["walk_left"];
+        assert_eq!(left.files.as_ref().unwrap().len(), 2);
+        assert!(left.reverse);
+        assert!(left.mirror);
+        assert_eq!(left.mode, None);
+        assert_eq!(left.sprite_size, Some((30.0, 30.0)));
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(CharacterManifest::parse("[clips.walk]\nframes = 3").is_err());
+        assert!(CharacterManifest::parse("size = [60.0, 60.0]").is_err());
+        assert!(CharacterManifest::parse("[clips.walk]\nmode = \"bounce\"").is_err());
+    }
+
+    #[test]
+    fn parses_mirrored_clips() {
+        let manifest = CharacterManifest::parse(
+            r#"
+            [clips.walk_left]
+            mirror_of = "walk_right"
+            reverse = true
+            "#,
+        )
+        .unwrap();
+
+        let left = &manifest.clips["walk_left"];
+        assert_eq!(left.mirror_of.as_deref(), Some("walk_right"));
+        assert!(left.reverse);
+        assert_eq!(left.directory, None);
+        assert_eq!(left.mode, None);
+    }
+
+    #[test]
+    fn parses_fixed_and_per_frame_values() {
+        let manifest = CharacterManifest::parse(
+            r#"
+            [clips.walk]
+            directory = "walk"
+            hitbox = [0.25, 0.1, 0.5, 0.9]
+            foot = [[0.5, 1.0], [0.4, 0.95]]
+            "#,
+        )
+        .unwrap();
+
+        let walk = &manifest.clips["walk"];
+        let hitbox = walk.hitbox.as_ref().unwrap();
+        assert!(matches!(hitbox, FrameValues::Fixed(_)));
+        assert_eq!(hitbox.get(7), (0.25, 0.1, 0.5, 0.9));
+        assert_eq!(walk.foot.as_ref().unwrap().get(1), (0.4, 0.95));
+    }
+
+    #[test]
+    fn per_frame_values_cover_every_frame() {
+        let fixed = FrameValues::Fixed((0.5, 1.0));
+        assert!(fixed.check(40).is_ok());
+        let per_frame = FrameValues::PerFrame(vec![(0.5, 1.0), (0.4, 1.0)]);
+        assert!(per_frame.check(2).is_ok());
+        assert_eq!(per_frame.check(3), Err("2 values for 3 frames".to_string()));
+    }
+}
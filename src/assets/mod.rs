@@ -0,0 +1,14 @@
+pub mod accessory;
+pub mod animated;
+pub mod aseprite;
+pub mod clip;
+pub mod embedded;
+pub mod error;
+pub mod locator;
+pub mod manifest;
+pub mod palette;
+pub mod recolor;
+pub mod registry;
+pub mod sheet;
+pub mod sprite;
+pub mod watch;
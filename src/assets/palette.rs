@@ -0,0 +1,117 @@
+use std::{fs::read_to_string, path::Path};
+
+// The palette the shipped sprites were made with
+const DEFAULT_PALETTE: &str = include_str!("../../assets/Palettes/penguins.gpl");
+
+// A fixed set of colours every pixel is snapped to
+#[derive(Debug, Clone)]
+pub struct Palette {
+    colors: Vec<[u8; 3]>,
+}
+
+impl Palette {
+    // Reads a GIMP (.gpl), hex (.hex, one RRGGBB per line), Paint.NET (.txt)
+    // or JSON (.json, [[r, g, b], ...]) palette
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = read_to_string(path).map_err(|error| error.to_string())?;
+        Self::parse(&source, &path.to_string_lossy())
+    }
+
+    // Parses `source` in the format the extension of `file` names
+    pub fn parse(source: &str, file: &str) -> Result<Self, String> {
+        let extension = Path::new(file)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        let colors = match extension.as_str() {
+            "gpl" => parse_gpl(source)?,
+            "hex" | "txt" => parse_hex(source)?,
+            "json" => serde_json::from_str(source).map_err(|error| error.to_string())?,
+            _ => {
+                return Err(format!(
+                    "unknown palette format {:?}, expected .gpl, .hex, .txt or .json",
+                    extension
+                ))
+            }
+        };
+
+        Self::new(colors)
+    }
+
+    pub fn new(colors: Vec<[u8; 3]>) -> Result<Self, String> {
+        if colors.is_empty() {
+            return Err("palette has no colours".to_string());
+        }
+        Ok(Self { colors })
+    }
+
+    pub fn colors(&self) -> &[[u8; 3]] {
+        &self.colors
+    }
+
+    // The palette colour closest to `color` by RGB distance
+    pub fn nearest(&self, color: [f32; 3]) -> [u8; 3] {
+        let distance = |candidate: &[u8; 3]| {
+            (0..3)
+                .map(|channel| (color[channel] - candidate[channel] as f32).powi(2))
+                .sum::<f32>()
+        };
+
+        *self
+            .colors
+            .iter()
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .expect("a palette always has colours")
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        let colors = parse_gpl(DEFAULT_PALETTE).expect("the built-in palette is valid");
+        Self::new(colors).expect("the built-in palette has colours")
+    }
+}
+
+fn parse_gpl(source: &str) -> Result<Vec<[u8; 3]>, String> {
+    let mut lines = source.lines();
+    if lines.next().map(str::trim) != Some("GIMP Palette") {
+        return Err("missing \"GIMP Palette\" header".to_string());
+    }
+
+    lines
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter(|line| !line.starts_with("Name:") && !line.starts_with("Columns:"))
+        .map(|line| {
+            let channels = line
+                .split_whitespace()
+                .take(3)
+                .map(|channel| channel.parse::<u8>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| format!("invalid colour {:?}", line))?;
+            <[u8; 3]>::try_from(channels).map_err(|_| format!("invalid colour {:?}", line))
+        })
+        .collect()
+}
+
+// RRGGBB per line; Paint.NET writes AARRGGBB and `;` comments
+fn parse_hex(source: &str) -> Result<Vec<[u8; 3]>, String> {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(';'))
+        .map(|line| {
+            let hex = line.trim_start_matches('#');
+            let rgb = match (hex.is_ascii(), hex.len()) {
+                (true, 6) => hex,
+                (true, 8) => &hex[2..],
+                _ => return Err(format!("invalid colour {:?}", line)),
+            };
+            let value =
+                u32::from_str_radix(rgb, 16).map_err(|_| format!("invalid colour {:?}", line))?;
+            let [_, red, green, blue] = value.to_be_bytes();
+            Ok([red, green, blue])
+        })
+        .collect()
+}
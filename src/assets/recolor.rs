@@ -0,0 +1,181 @@
+use super::error::AssetError;
+use super::locator::AssetDir;
+use super::manifest::{PalettesManifest, PALETTES_FILE};
+use super::palette::Palette;
+use crate::random;
+use ::image::RgbaImage;
+use rand::Rng;
+use std::{collections::HashMap, rc::Rc};
+
+// Folder next to the character folders that holds the palette swaps
+pub const PALETTES_FOLDER: &str = "Palettes";
+
+// Pixels this close (RGB distance) to a source colour count as that colour,
+// so anti-aliased edges follow the swap as well
+const TOLERANCE: f32 = 24.0;
+
+// Replaces every colour of a source palette by the colour at the same index
+// of another palette
+#[derive(Debug)]
+pub struct Recolor {
+    from: Vec<[u8; 3]>,
+    to: Vec<[u8; 3]>,
+}
+
+impl Recolor {
+    pub fn new(from: &Palette, to: &Palette) -> Result<Self, String> {
+        if from.colors().len() != to.colors().len() {
+            return Err(format!(
+                "has {} colours but the source palette has {}",
+                to.colors().len(),
+                from.colors().len()
+            ));
+        }
+        Ok(Self {
+            from: from.colors().to_vec(),
+            to: to.colors().to_vec(),
+        })
+    }
+
+    // Close matches move by the same offset as the colour they match, which
+    // keeps the shading of blended pixels
+    fn color(&self, color: [u8; 3]) -> [u8; 3] {
+        let distance = |candidate: &[u8; 3]| {
+            (0..3)
+                .map(|channel| (color[channel] as f32 - candidate[channel] as f32).powi(2))
+                .sum::<f32>()
+                .sqrt()
+        };
+        let Some((idx, closest)) = self
+            .from
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| distance(a).total_cmp(&distance(b)))
+        else {
+            return color;
+        };
+        if distance(closest) > TOLERANCE {
+            return color;
+        }
+
+        let target = self.to[idx];
+        [0, 1, 2].map(|channel| {
+            let shifted = color[channel] as i16 + target[channel] as i16 - closest[channel] as i16;
+            shifted.clamp(0, 255) as u8
+        })
+    }
+
+    pub fn image(&self, rgba: &mut RgbaImage) {
+        // sprites use few colours, each is looked up once
+        let mut mapped: HashMap<[u8; 3], [u8; 3]> = HashMap::new();
+        for pixel in rgba.pixels_mut() {
+            if pixel[3] == 0 {
+                continue;
+            }
+            let color = [pixel[0], pixel[1], pixel[2]];
+            let [red, green, blue] = *mapped.entry(color).or_insert_with(|| self.color(color));
+            *pixel = ::image::Rgba([red, green, blue, pixel[3]]);
+        }
+    }
+}
+
+// The palette swaps a spawn can get, by name
+#[derive(Debug, Default)]
+pub struct PaletteSwaps {
+    swaps: Vec<(String, f32, Rc<Recolor>)>,
+}
+
+impl PaletteSwaps {
+    // Swaps that fail to load are left out; all problems are returned
+    pub fn load(dir: &AssetDir) -> (Self, Vec<AssetError>) {
+        let manifest_path = dir.display(PALETTES_FILE);
+        let source = match dir.read(PALETTES_FILE) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            // a pack without swaps keeps the original colours
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return (Self::default(), Vec::new())
+            }
+            Err(source) => {
+                let error = AssetError::ManifestUnreadable {
+                    path: manifest_path,
+                    source,
+                };
+                return (Self::default(), vec![error]);
+            }
+        };
+        let manifest = match PalettesManifest::parse(&source) {
+            Ok(manifest) => manifest,
+            Err(source) => {
+                let error = AssetError::ManifestInvalid {
+                    path: manifest_path,
+                    source,
+                };
+                return (Self::default(), vec![error]);
+            }
+        };
+
+        let palette = |file: &str| {
+            let bytes = dir.read(file).map_err(|error| error.to_string())?;
+            Palette::parse(&String::from_utf8_lossy(&bytes), file)
+        };
+        let from = match palette(&manifest.source) {
+            Ok(from) => from,
+            Err(reason) => {
+                let error = AssetError::PaletteInvalid {
+                    path: dir.display(&manifest.source),
+                    reason,
+                };
+                return (Self::default(), vec![error]);
+            }
+        };
+
+        let mut errors = Vec::new();
+        let mut swaps = Vec::new();
+        for (name, swap) in manifest.swaps {
+            let (path, recolor) = match (0.0..=1.0).contains(&swap.chance) {
+                true => (
+                    dir.display(&swap.palette),
+                    palette(&swap.palette).and_then(|to| Recolor::new(&from, &to)),
+                ),
+                false => (
+                    manifest_path.clone(),
+                    Err(format!("chance {} is not between 0 and 1", swap.chance)),
+                ),
+            };
+            match recolor {
+                Ok(recolor) => swaps.push((name, swap.chance, Rc::new(recolor))),
+                Err(reason) => errors.push(AssetError::SwapInvalid {
+                    path,
+                    swap: name,
+                    reason,
+                }),
+            }
+        }
+
+        (Self { swaps }, errors)
+    }
+
+    pub fn get(&self, name: &str) -> Option<Rc<Recolor>> {
+        self.swaps
+            .iter()
+            .find(|(swap, _, _)| swap == name)
+            .map(|(_, _, recolor)| recolor.clone())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.swaps.iter().map(|(name, _, _)| name.as_str())
+    }
+
+    // One roll shared out between the swaps by their chances; what is left
+    // over (None) keeps the original colours
+    pub fn pick(&self) -> Option<String> {
+        let mut roll = random::rng().random_range(0.0..1.0);
+        for (name, chance, _) in &self.swaps {
+            if roll < *chance {
+                return Some(name.clone());
+            }
+            roll -= chance;
+        }
+        None
+    }
+}
@@ -0,0 +1,1261 @@
+use super::accessory::{Accessories, Accessory, ACCESSORIES_FOLDER};
+use super::animated::{decode_animation, AnimatedFrame};
+use super::aseprite::AsepriteExport;
+use super::clip::{Clip, FrameMetadata, Frames, TICK_MS};
+use super::error::{report, AssetError};
+use super::locator::{AssetDir, AssetLocator};
+use super::manifest::{
+    AccessoriesManifest, CharacterManifest, ClipManifest, PlayMode, SheetRects, ACCESSORIES_FILE,
+    DEFAULT_FRAME_DURATION, MANIFEST_FILE,
+};
+use super::recolor::{PaletteSwaps, PALETTES_FOLDER};
+use super::sheet::{crop, parse_rects, FrameRect};
+use super::sprite::{encode_png, Sprite, VectorSprite};
+use crate::animations::balloon_animation::balloon_animation_assets::{
+    get_balloon_variants, BalloonVariant, BALLOON_CHARACTER,
+};
+use ::image::{imageops, RgbaImage};
+use iced::widget::image;
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    hash::{Hash, Hasher},
+    io,
+    io::Cursor,
+    rc::Rc,
+};
+
+// Single frame clips paired with the file name they were loaded from
+pub type LooseImages = Rc<[(String, Clip)]>;
+
+// Source frames and a palette swap name
+type RecolorKey = (SharedFrames, String);
+
+// Frames compared by identity. Holding them keeps their allocation, so no
+// other frames can turn up at the same address while they are a key.
+#[derive(Clone)]
+struct SharedFrames(Frames);
+
+impl PartialEq for SharedFrames {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedFrames {}
+
+impl Hash for SharedFrames {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Rc::as_ptr(&self.0).hash(state);
+    }
+}
+
+const DEFAULT_SPRITE_SIZE: (f32, f32) = (60.0, 60.0);
+
+// The clips of one character folder, keyed by their manifest name
+#[derive(Debug)]
+pub struct Character {
+    clips: HashMap<String, Clip>,
+    sprite_size: (f32, f32),
+}
+
+impl Character {
+    // Clips that are not declared are replaced by a placeholder sprite
+    pub fn clip(&self, name: &str) -> Clip {
+        self.clips
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| Clip::placeholder(self.sprite_size))
+    }
+
+    pub fn clip_names(&self) -> impl Iterator<Item = &str> {
+        self.clips.keys().map(String::as_str)
+    }
+
+    pub fn sprite_size(&self) -> (f32, f32) {
+        self.sprite_size
+    }
+}
+
+impl Default for Character {
+    fn default() -> Self {
+        Self {
+            clips: HashMap::new(),
+            sprite_size: DEFAULT_SPRITE_SIZE,
+        }
+    }
+}
+
+// Created once at startup and shared by every animation. Each character is
+// loaded the first time it is requested: its manifest is parsed and every
+// frame decoded once. Later requests only clone reference-counted handles,
+// so spawning another penguin costs no I/O. Loading problems are printed
+// (or collected, see `collecting`) and the affected clips fall back to a
+// placeholder sprite. Palette swapped frames are shared the same way, see
+// `recolor`, and so are the accessories and the balloon colours.
+pub struct AssetRegistry {
+    locator: AssetLocator,
+    characters: RefCell<HashMap<String, Rc<Character>>>,
+    loose_images: RefCell<HashMap<String, LooseImages>>,
+    palette_swaps: RefCell<Option<Rc<PaletteSwaps>>>,
+    accessories: RefCell<Option<Rc<Accessories>>>,
+    balloon_variants: RefCell<Option<Rc<[BalloonVariant]>>>,
+    // recoloured frames keyed by the frames they were made from and the
+    // swap name
+    recolored: RefCell<HashMap<RecolorKey, Frames>>,
+    collected: Option<RefCell<Vec<AssetError>>>,
+}
+
+impl AssetRegistry {
+    pub fn new(locator: AssetLocator) -> Self {
+        Self {
+            locator,
+            characters: RefCell::new(HashMap::new()),
+            loose_images: RefCell::new(HashMap::new()),
+            palette_swaps: RefCell::new(None),
+            accessories: RefCell::new(None),
+            balloon_variants: RefCell::new(None),
+            recolored: RefCell::new(HashMap::new()),
+            collected: None,
+        }
+    }
+
+    // Keeps loading problems for `take_errors` instead of printing them
+    pub fn collecting(locator: AssetLocator) -> Self {
+        Self {
+            collected: Some(RefCell::new(Vec::new())),
+            ..Self::new(locator)
+        }
+    }
+
+    pub fn report(&self, errors: Vec<AssetError>) {
+        match &self.collected {
+            Some(collected) => collected.borrow_mut().extend(errors),
+            None => report(&errors),
+        }
+    }
+
+    pub fn take_errors(&self) -> Vec<AssetError> {
+        self.collected
+            .as_ref()
+            .map(|collected| collected.take())
+            .unwrap_or_default()
+    }
+
+    pub fn locator(&self) -> &AssetLocator {
+        &self.locator
+    }
+
+    pub fn character(&self, folder: &str) -> Rc<Character> {
+        if let Some(character) = self.characters.borrow().get(folder) {
+            return character.clone();
+        }
+
+        let (character, errors) = load_character(&self.locator.open(folder));
+        self.report(errors);
+
+        let character = Rc::new(character);
+        self.characters
+            .borrow_mut()
+            .insert(folder.to_string(), character.clone());
+        character
+    }
+
+    // Drops everything cached for `folder` and loads it again from disk
+    pub fn reload(&self, folder: &str) {
+        self.recolored.borrow_mut().clear();
+        match folder {
+            PALETTES_FOLDER => {
+                self.palette_swaps.take();
+                self.palette_swaps();
+            }
+            ACCESSORIES_FOLDER => {
+                self.accessories.take();
+                self.accessories();
+            }
+            _ => {
+                self.characters.borrow_mut().remove(folder);
+                self.loose_images.borrow_mut().remove(folder);
+                self.character(folder);
+                if folder == BALLOON_CHARACTER {
+                    self.balloon_variants.take();
+                    self.balloon_variants();
+                }
+            }
+        }
+    }
+
+    pub fn accessories(&self) -> Rc<Accessories> {
+        if let Some(accessories) = &*self.accessories.borrow() {
+            return accessories.clone();
+        }
+
+        let (accessories, errors) = load_accessories(&self.locator.open(ACCESSORIES_FOLDER));
+        self.report(errors);
+
+        let accessories = Rc::new(accessories);
+        *self.accessories.borrow_mut() = Some(accessories.clone());
+        accessories
+    }
+
+    // The complete balloon colours of the pack, see `get_balloon_variants`
+    pub fn balloon_variants(&self) -> Rc<[BalloonVariant]> {
+        if let Some(variants) = &*self.balloon_variants.borrow() {
+            return variants.clone();
+        }
+
+        let (variants, errors) = get_balloon_variants(self);
+        self.report(errors);
+
+        let variants: Rc<[BalloonVariant]> = variants.into();
+        *self.balloon_variants.borrow_mut() = Some(variants.clone());
+        variants
+    }
+
+    pub fn palette_swaps(&self) -> Rc<PaletteSwaps> {
+        if let Some(swaps) = &*self.palette_swaps.borrow() {
+            return swaps.clone();
+        }
+
+        let (swaps, errors) = PaletteSwaps::load(&self.locator.open(PALETTES_FOLDER));
+        self.report(errors);
+
+        let swaps = Rc::new(swaps);
+        *self.palette_swaps.borrow_mut() = Some(swaps.clone());
+        swaps
+    }
+
+    // `clip` with the colours of palette swap `swap`. Frames are recoloured
+    // the first time they are drawn and shared by every clip recoloured the
+    // same way afterwards. Unknown swaps keep the original colours.
+    pub fn recolor(&self, clip: Clip, swap: Option<&str>) -> Clip {
+        let Some(recolor) = swap.and_then(|swap| self.palette_swaps().get(swap)) else {
+            return clip;
+        };
+        let key = (
+            SharedFrames(clip.frames.clone()),
+            swap.unwrap_or_default().to_string(),
+        );
+        if let Some(frames) = self.recolored.borrow().get(&key) {
+            return clip.with_frames(frames.clone());
+        }
+
+        let frames: Frames = clip
+            .frames
+            .iter()
+            .map(|frame| frame.recolored(recolor.clone()))
+            .collect();
+        self.recolored.borrow_mut().insert(key, frames.clone());
+        clip.with_frames(frames)
+    }
+
+    pub fn clip(&self, folder: &str, clip: &str) -> Clip {
+        self.character(folder).clip(clip)
+    }
+
+    // Human readable location of `relative` inside `folder`
+    pub fn display(&self, folder: &str, relative: &str) -> String {
+        self.locator.open(folder).display(relative)
+    }
+
+    // Every image at the top of `folder` as a single frame clip, paired with
+    // its file name. Used by characters that find their sprites by name
+    // instead of through manifest clips.
+    pub fn loose_images(&self, folder: &str) -> LooseImages {
+        if let Some(images) = self.loose_images.borrow().get(folder) {
+            return images.clone();
+        }
+
+        let dir = self.locator.open(folder);
+        let sprite_size = self.character(folder).sprite_size();
+        let mut decoder = FrameDecoder::default();
+        let mut errors = Vec::new();
+        let files = dir.list("").unwrap_or_else(|source| {
+            errors.push(AssetError::DirectoryUnreadable {
+                path: dir.display(""),
+                source,
+            });
+            Vec::new()
+        });
+        let images: LooseImages = files
+            .into_iter()
+            .filter(|file| ::image::ImageFormat::from_path(file).is_ok() || is_svg(file))
+            .filter_map(
+                |file| match decoder.frame(&dir, &FrameSpec::whole(file.clone()), false) {
+                    Ok(handle) => {
+                        let clip =
+                            Clip::new(vec![handle], vec![TICK_MS], PlayMode::Loop, sprite_size);
+                        Some((file, clip))
+                    }
+                    Err(error) => {
+                        errors.push(error);
+                        None
+                    }
+                },
+            )
+            .collect();
+        self.report(errors);
+
+        self.loose_images
+            .borrow_mut()
+            .insert(folder.to_string(), images.clone());
+        images
+    }
+
+    // Loads `folder` up front and reports any of `clips` it does not declare
+    pub fn preload(&self, folder: &str, clips: &[&str]) {
+        let character = self.character(folder);
+        let dir = self.locator.open(folder);
+        let errors: Vec<AssetError> = clips
+            .iter()
+            .filter(|clip| !character.clips.contains_key(**clip))
+            .map(|clip| AssetError::ClipMissing {
+                path: dir.display(MANIFEST_FILE),
+                clip: clip.to_string(),
+            })
+            .collect();
+        self.report(errors);
+    }
+}
+
+// Loads every clip declared by the manifest of `dir`. Clips that end up
+// without frames are replaced by a placeholder; all problems are returned.
+pub fn load_character(dir: &AssetDir) -> (Character, Vec<AssetError>) {
+    let mut errors = Vec::new();
+    let manifest_path = dir.display(MANIFEST_FILE);
+
+    let manifest = match dir.read(MANIFEST_FILE) {
+        Ok(bytes) => match CharacterManifest::parse(&String::from_utf8_lossy(&bytes)) {
+            Ok(manifest) => manifest,
+            Err(source) => {
+                errors.push(AssetError::ManifestInvalid {
+                    path: manifest_path,
+                    source,
+                });
+                return (Character::default(), errors);
+            }
+        },
+        // a folder without manifest simply declares no clips
+        Err(source) if source.kind() == io::ErrorKind::NotFound => {
+            return (Character::default(), errors);
+        }
+        Err(source) => {
+            errors.push(AssetError::ManifestUnreadable {
+                path: manifest_path,
+                source,
+            });
+            return (Character::default(), errors);
+        }
+    };
+
+    let character_sprite_size = manifest.sprite_size.unwrap_or(DEFAULT_SPRITE_SIZE);
+
+    let mut decoder = FrameDecoder::default();
+
+    // every tag of an Aseprite export is a clip unless declared explicitly
+    let mut declared = manifest.clips.clone();
+    for json in &manifest.aseprite {
+        let export = match decoder.export(dir, json) {
+            Ok(export) => export,
+            Err(error) => {
+                errors.push(error);
+                continue;
+            }
+        };
+        let names = match export.tags.is_empty() {
+            true => vec![file_stem(json).to_string()],
+            false => export.tags.iter().map(|tag| tag.name.clone()).collect(),
+        };
+        for name in names {
+            declared.entry(name).or_insert_with(|| ClipManifest {
+                aseprite: Some(json.clone()),
+                ..ClipManifest::default()
+            });
+        }
+    }
+
+    let clips = resolve_mirrors(&declared, &manifest_path, &mut errors)
+        .iter()
+        .map(|(name, clip)| {
+            let sprite_size = clip.sprite_size.unwrap_or(character_sprite_size);
+            let mut loaded = load_clip(dir, name, clip, sprite_size, &mut decoder, &mut errors);
+            if loaded.frames.is_empty() {
+                errors.push(AssetError::ClipEmpty {
+                    path: manifest_path.clone(),
+                    clip: name.clone(),
+                });
+                loaded = Clip::placeholder(sprite_size);
+            }
+            (name.clone(), loaded)
+        })
+        .collect();
+
+    (
+        Character {
+            clips,
+            sprite_size: character_sprite_size,
+        },
+        errors,
+    )
+}
+
+// Loads every accessory declared in `dir`; the ones that fail to load are
+// left out and all problems are returned
+pub fn load_accessories(dir: &AssetDir) -> (Accessories, Vec<AssetError>) {
+    let mut errors = Vec::new();
+    let manifest_path = dir.display(ACCESSORIES_FILE);
+
+    let manifest = match dir.read(ACCESSORIES_FILE) {
+        Ok(bytes) => match AccessoriesManifest::parse(&String::from_utf8_lossy(&bytes)) {
+            Ok(manifest) => manifest,
+            Err(source) => {
+                errors.push(AssetError::ManifestInvalid {
+                    path: manifest_path,
+                    source,
+                });
+                return (Accessories::default(), errors);
+            }
+        },
+        // a pack without accessories has penguins wear nothing
+        Err(source) if source.kind() == io::ErrorKind::NotFound => {
+            return (Accessories::default(), errors);
+        }
+        Err(source) => {
+            errors.push(AssetError::ManifestUnreadable {
+                path: manifest_path,
+                source,
+            });
+            return (Accessories::default(), errors);
+        }
+    };
+
+    let mut decoder = FrameDecoder::default();
+    let mut accessories = Vec::new();
+    for (name, accessory) in manifest.accessories {
+        if !(0.0..=1.0).contains(&accessory.chance) {
+            errors.push(AssetError::AccessoryInvalid {
+                path: manifest_path.clone(),
+                accessory: name,
+                reason: format!("chance {} is not between 0 and 1", accessory.chance),
+            });
+            continue;
+        }
+
+        let spec = FrameSpec::whole(accessory.image.clone());
+        let sprites = decoder
+            .frame(dir, &spec, false)
+            .and_then(|sprite| Ok((sprite, decoder.frame(dir, &spec, true)?)));
+        match sprites {
+            Ok((sprite, mirrored)) => accessories.push(Rc::new(Accessory {
+                name,
+                anchor: accessory.anchor,
+                size: accessory.size,
+                pivot: accessory.pivot,
+                layer: accessory.layer,
+                chance: accessory.chance,
+                sprite,
+                mirrored,
+            })),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    (Accessories::new(accessories), errors)
+}
+
+// Replaces every `mirror_of` clip by a flipped copy of the clip it mirrors,
+// keeping the timing, mode and size the mirroring clip sets itself. Clips
+// that mirror a missing clip or end up mirroring themselves are left out.
+fn resolve_mirrors(
+    clips: &HashMap<String, ClipManifest>,
+    manifest_path: &str,
+    errors: &mut Vec<AssetError>,
+) -> HashMap<String, ClipManifest> {
+    let mut resolved = HashMap::new();
+
+    'clips: for (name, clip) in clips {
+        let mut source_name = name;
+        let mut source = clip;
+        let mut flipped = false;
+        let mut visited = vec![name];
+        while let Some(target) = &source.mirror_of {
+            let invalid = |reason| AssetError::MirrorInvalid {
+                path: manifest_path.to_string(),
+                clip: name.clone(),
+                reason,
+            };
+            if visited.contains(&target) {
+                errors.push(invalid(format!("mirroring loops back to {:?}", target)));
+                continue 'clips;
+            }
+            let Some(target_clip) = clips.get(target) else {
+                errors.push(invalid(format!("clip {:?} is not declared", target)));
+                continue 'clips;
+            };
+            visited.push(target);
+            source_name = target;
+            source = target_clip;
+            flipped = !flipped;
+        }
+
+        if source_name == name {
+            resolved.insert(name.clone(), clip.clone());
+            continue;
+        }
+
+        let mut mirrored = ClipManifest {
+            mirror_of: None,
+            mirror: source.mirror != flipped,
+            reverse: source.reverse != clip.reverse,
+            frame_count: clip.frame_count.or(source.frame_count),
+            frame_duration: clip.frame_duration.or(source.frame_duration),
+            durations: clip.durations.clone().or_else(|| source.durations.clone()),
+            mode: clip.mode.or(source.mode),
+            sprite_size: clip.sprite_size.or(source.sprite_size),
+            anchors: match clip.anchors.is_empty() {
+                true => source.anchors.clone(),
+                false => clip.anchors.clone(),
+            },
+            hitbox: clip.hitbox.clone().or_else(|| source.hitbox.clone()),
+            foot: clip.foot.clone().or_else(|| source.foot.clone()),
+            ..source.clone()
+        };
+        // an Aseprite clip picks its tag by name, which is the source's name
+        if mirrored.aseprite.is_some() && mirrored.tag.is_none() {
+            mirrored.tag = Some(source_name.clone());
+        }
+        resolved.insert(name.clone(), mirrored);
+    }
+
+    resolved
+}
+
+// One frame to load from `file`
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct FrameSpec {
+    file: String,
+    source: FrameSource,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum FrameSource {
+    Whole,
+    // A rectangle of a sprite sheet
+    Rect(FrameRect),
+    // A trimmed Aseprite frame, put back at `offset` on a `canvas` sized frame
+    Trimmed {
+        rect: FrameRect,
+        offset: (u32, u32),
+        canvas: (u32, u32),
+    },
+    // The n-th frame of an animated GIF or APNG
+    Animated(usize),
+}
+
+impl FrameSpec {
+    fn whole(file: String) -> Self {
+        Self {
+            file,
+            source: FrameSource::Whole,
+        }
+    }
+}
+
+// The frames of a clip with the durations their source gives them, plus the
+// playback an Aseprite tag asks for
+#[derive(Default)]
+struct SourceFrames {
+    frames: Vec<(FrameSpec, Option<u32>)>,
+    playback: Option<(PlayMode, bool)>,
+}
+
+// Reads every file and builds every frame of a character only once, even
+// when several clips share them. Frames used as they are keep their encoded
+// bytes (the renderer decodes them once per handle) after a trial decode;
+// only cropped or mirrored frames are decoded here and stored re-encoded as
+// PNG, so a whole character never sits in memory as raw pixels. SVG frames
+// are parsed here and rasterised when drawn.
+#[derive(Default)]
+struct FrameDecoder {
+    files: HashMap<String, Cow<'static, [u8]>>,
+    images: HashMap<String, Rc<RgbaImage>>,
+    // files that decoded fine, their pixels dropped again
+    decodable: HashSet<String>,
+    animations: HashMap<String, Rc<[AnimatedFrame]>>,
+    exports: HashMap<String, Rc<AsepriteExport>>,
+    sprites: HashMap<(FrameSpec, bool), Sprite>,
+}
+
+impl FrameDecoder {
+    fn bytes(&mut self, dir: &AssetDir, file: &str) -> Result<Cow<'static, [u8]>, AssetError> {
+        if let Some(bytes) = self.files.get(file) {
+            return Ok(bytes.clone());
+        }
+
+        let bytes = dir
+            .read(file)
+            .map_err(|source| AssetError::FrameUnreadable {
+                path: dir.display(file),
+                source,
+            })?;
+        self.files.insert(file.to_string(), bytes.clone());
+        Ok(bytes)
+    }
+
+    // Width and height read from the image header only
+    fn dimensions(&mut self, dir: &AssetDir, file: &str) -> Result<(u32, u32), AssetError> {
+        if let Some(image) = self.images.get(file) {
+            return Ok(image.dimensions());
+        }
+
+        let bytes = self.bytes(dir, file)?;
+        ::image::ImageReader::new(Cursor::new(&bytes[..]))
+            .with_guessed_format()
+            .map_err(::image::ImageError::IoError)
+            .and_then(|reader| reader.into_dimensions())
+            .map_err(|source| AssetError::FrameUndecodable {
+                path: dir.display(file),
+                source,
+            })
+    }
+
+    // Decodes all of `file` once, so one broken past its header is reported
+    // here rather than drawn blank
+    fn check(&mut self, dir: &AssetDir, file: &str) -> Result<(), AssetError> {
+        if self.images.contains_key(file) || self.decodable.contains(file) {
+            return Ok(());
+        }
+
+        let bytes = self.bytes(dir, file)?;
+        ::image::load_from_memory(&bytes).map_err(|source| AssetError::FrameUndecodable {
+            path: dir.display(file),
+            source,
+        })?;
+        self.decodable.insert(file.to_string());
+        Ok(())
+    }
+
+    fn image(&mut self, dir: &AssetDir, file: &str) -> Result<Rc<RgbaImage>, AssetError> {
+        if let Some(image) = self.images.get(file) {
+            return Ok(image.clone());
+        }
+
+        let bytes = self.bytes(dir, file)?;
+        let image = ::image::load_from_memory(&bytes)
+            .map_err(|source| AssetError::FrameUndecodable {
+                path: dir.display(file),
+                source,
+            })?
+            .to_rgba8();
+
+        let image = Rc::new(image);
+        self.images.insert(file.to_string(), image.clone());
+        Ok(image)
+    }
+
+    fn animation(&mut self, dir: &AssetDir, file: &str) -> Result<Rc<[AnimatedFrame]>, AssetError> {
+        if let Some(frames) = self.animations.get(file) {
+            return Ok(frames.clone());
+        }
+
+        let bytes = self.bytes(dir, file)?;
+        let frames: Rc<[AnimatedFrame]> = decode_animation(&bytes)
+            .map_err(|source| AssetError::FrameUndecodable {
+                path: dir.display(file),
+                source,
+            })?
+            .into();
+        self.animations.insert(file.to_string(), frames.clone());
+        Ok(frames)
+    }
+
+    fn export(&mut self, dir: &AssetDir, json: &str) -> Result<Rc<AsepriteExport>, AssetError> {
+        if let Some(export) = self.exports.get(json) {
+            return Ok(export.clone());
+        }
+
+        let bytes = self.bytes(dir, json)?;
+        let export =
+            AsepriteExport::parse(&bytes).map_err(|source| AssetError::AsepriteInvalid {
+                path: dir.display(json),
+                source,
+            })?;
+        let export = Rc::new(export);
+        self.exports.insert(json.to_string(), export.clone());
+        Ok(export)
+    }
+
+    fn frame(
+        &mut self,
+        dir: &AssetDir,
+        spec: &FrameSpec,
+        mirror: bool,
+    ) -> Result<Sprite, AssetError> {
+        let key = (spec.clone(), mirror);
+        if let Some(sprite) = self.sprites.get(&key) {
+            return Ok(sprite.clone());
+        }
+
+        let sprite = match is_svg(&spec.file) {
+            true => Sprite::Vector(Rc::new(self.vector(dir, spec, mirror)?)),
+            false => Sprite::Raster(self.raster(dir, spec, mirror)?),
+        };
+
+        self.sprites.insert(key, sprite.clone());
+        Ok(sprite)
+    }
+
+    fn vector(
+        &mut self,
+        dir: &AssetDir,
+        spec: &FrameSpec,
+        mirror: bool,
+    ) -> Result<VectorSprite, AssetError> {
+        if spec.source != FrameSource::Whole {
+            return Err(AssetError::SheetInvalid {
+                path: dir.display(&spec.file),
+                reason: "SVG frames cannot be cut from a sheet or animation".to_string(),
+            });
+        }
+
+        let bytes = self.bytes(dir, &spec.file)?;
+        VectorSprite::parse(&bytes, mirror).map_err(|source| AssetError::SvgInvalid {
+            path: dir.display(&spec.file),
+            source,
+        })
+    }
+
+    fn raster(
+        &mut self,
+        dir: &AssetDir,
+        spec: &FrameSpec,
+        mirror: bool,
+    ) -> Result<image::Handle, AssetError> {
+        let handle = if spec.source == FrameSource::Whole && !mirror {
+            self.check(dir, &spec.file)?;
+            match self.bytes(dir, &spec.file)? {
+                Cow::Borrowed(bytes) => image::Handle::from_bytes(bytes),
+                Cow::Owned(bytes) => image::Handle::from_bytes(bytes),
+            }
+        } else {
+            let sheet_invalid = |reason| AssetError::SheetInvalid {
+                path: dir.display(&spec.file),
+                reason,
+            };
+            let mut rgba = match &spec.source {
+                FrameSource::Whole => (*self.image(dir, &spec.file)?).clone(),
+                FrameSource::Rect(rect) => {
+                    crop(&*self.image(dir, &spec.file)?, rect).map_err(sheet_invalid)?
+                }
+                FrameSource::Trimmed {
+                    rect,
+                    offset,
+                    canvas,
+                } => {
+                    let trimmed =
+                        crop(&*self.image(dir, &spec.file)?, rect).map_err(sheet_invalid)?;
+                    let mut untrimmed = RgbaImage::new(canvas.0, canvas.1);
+                    imageops::replace(&mut untrimmed, &trimmed, offset.0 as i64, offset.1 as i64);
+                    untrimmed
+                }
+                FrameSource::Animated(idx) => self
+                    .animation(dir, &spec.file)?
+                    .get(*idx)
+                    .map(|(frame, _)| frame.clone())
+                    .ok_or_else(|| sheet_invalid(format!("animation has no frame {}", idx)))?,
+            };
+            if mirror {
+                ::image::imageops::flip_horizontal_in_place(&mut rgba);
+            }
+            encode_png(&rgba)
+        };
+
+        Ok(handle)
+    }
+}
+
+fn is_svg(file: &str) -> bool {
+    file.to_lowercase().ends_with(".svg")
+}
+
+fn frame_specs(
+    dir: &AssetDir,
+    name: &str,
+    clip: &ClipManifest,
+    decoder: &mut FrameDecoder,
+) -> Result<SourceFrames, AssetError> {
+    let untimed = |specs: Vec<FrameSpec>| SourceFrames {
+        frames: specs.into_iter().map(|spec| (spec, None)).collect(),
+        playback: None,
+    };
+    let whole_files =
+        |files: Vec<String>| untimed(files.into_iter().map(FrameSpec::whole).collect());
+
+    if let Some(json) = &clip.aseprite {
+        let export = decoder.export(dir, json)?;
+        let tag = match &clip.tag {
+            Some(tag) => Some(export.tag(tag).ok_or_else(|| AssetError::TagMissing {
+                path: dir.display(json),
+                tag: tag.clone(),
+            })?),
+            None => export.tag(name),
+        };
+        let frames = match tag {
+            Some(tag) => export.frames.get(tag.from..=tag.to).unwrap_or_default(),
+            None => &export.frames[..],
+        };
+
+        let sheet = sibling(json, &export.image);
+        return Ok(SourceFrames {
+            frames: frames
+                .iter()
+                .map(|frame| {
+                    let source = match frame.untrimmed() {
+                        Some((offset, canvas)) => FrameSource::Trimmed {
+                            rect: frame.rect(),
+                            offset,
+                            canvas,
+                        },
+                        None => FrameSource::Rect(frame.rect()),
+                    };
+                    let spec = FrameSpec {
+                        file: sheet.clone(),
+                        source,
+                    };
+                    (spec, frame.duration)
+                })
+                .collect(),
+            playback: tag.map(|tag| tag.playback()),
+        });
+    }
+
+    if let Some(file) = &clip.animation {
+        let frames = decoder.animation(dir, file)?;
+        return Ok(SourceFrames {
+            frames: frames
+                .iter()
+                .enumerate()
+                .map(|(idx, (_, delay))| {
+                    let spec = FrameSpec {
+                        file: file.clone(),
+                        source: FrameSource::Animated(idx),
+                    };
+                    (spec, Some(*delay).filter(|delay| *delay > 0))
+                })
+                .collect(),
+            playback: None,
+        });
+    }
+
+    if let Some(sheet) = &clip.sheet {
+        let rects = match (&clip.grid, &clip.rects) {
+            (Some(grid), _) => {
+                let (width, height) = decoder.dimensions(dir, sheet)?;
+                grid.rects(width, height)
+                    .map_err(|reason| AssetError::SheetInvalid {
+                        path: dir.display(sheet),
+                        reason,
+                    })?
+            }
+            (None, Some(SheetRects::Inline(rects))) => rects.clone(),
+            (None, Some(SheetRects::File(file))) => {
+                let json = dir
+                    .read(file)
+                    .map_err(|source| AssetError::FrameUnreadable {
+                        path: dir.display(file),
+                        source,
+                    })?;
+                parse_rects(&json).map_err(|source| AssetError::RectsInvalid {
+                    path: dir.display(file),
+                    source,
+                })?
+            }
+            (None, None) => {
+                return Err(AssetError::SheetInvalid {
+                    path: dir.display(sheet),
+                    reason: "a sheet needs either `grid` or `rects`".to_string(),
+                })
+            }
+        };
+        return Ok(SourceFrames {
+            frames: rects
+                .into_iter()
+                .map(|rect| {
+                    let spec = FrameSpec {
+                        file: sheet.clone(),
+                        source: FrameSource::Rect(rect),
+                    };
+                    (spec, rect.duration)
+                })
+                .collect(),
+            playback: None,
+        });
+    }
+
+    match (&clip.files, &clip.directory) {
+        (Some(files), _) => Ok(whole_files(files.clone())),
+        (None, Some(directory)) => {
+            dir.list(directory)
+                .map(whole_files)
+                .map_err(|source| AssetError::DirectoryUnreadable {
+                    path: dir.display(directory),
+                    source,
+                })
+        }
+        (None, None) => Ok(SourceFrames::default()),
+    }
+}
+
+// `file` relative to the folder that holds `next_to`
+fn sibling(next_to: &str, file: &str) -> String {
+    match next_to.rsplit_once('/') {
+        Some((parent, _)) => format!("{}/{}", parent, file),
+        None => file.to_string(),
+    }
+}
+
+fn file_stem(file: &str) -> &str {
+    let name = file.rsplit('/').next().unwrap_or(file);
+    name.rsplit_once('.').map_or(name, |(stem, _)| stem)
+}
+
+fn load_clip(
+    dir: &AssetDir,
+    name: &str,
+    clip: &ClipManifest,
+    sprite_size: (f32, f32),
+    decoder: &mut FrameDecoder,
+    errors: &mut Vec<AssetError>,
+) -> Clip {
+    let SourceFrames {
+        frames: mut specs,
+        playback,
+    } = frame_specs(dir, name, clip, decoder).unwrap_or_else(|error| {
+        errors.push(error);
+        SourceFrames::default()
+    });
+    if let Some(frame_count) = clip.frame_count {
+        specs.truncate(frame_count);
+    }
+    let (tag_mode, tag_reverse) = playback.unwrap_or((PlayMode::Loop, false));
+
+    // unusable metadata is left out, the frames still load
+    let mut checked = |field: String, check: Result<(), String>| match check {
+        Ok(()) => true,
+        Err(reason) => {
+            errors.push(AssetError::MetadataInvalid {
+                path: dir.display(MANIFEST_FILE),
+                clip: name.to_string(),
+                field,
+                reason,
+            });
+            false
+        }
+    };
+    let mut metadata = FrameMetadata::default();
+    for (anchor, points) in &clip.anchors {
+        if checked(format!("anchor {:?}", anchor), points.check(specs.len())) {
+            metadata
+                .anchors
+                .insert(anchor.clone(), Vec::with_capacity(specs.len()));
+        }
+    }
+    let hitbox = clip.hitbox.as_ref().filter(|hitbox| {
+        let check = hitbox.check(specs.len()).and_then(|()| {
+            let empty = (0..specs.len())
+                .map(|idx| hitbox.get(idx))
+                .find(|(_, _, width, height)| *width <= 0.0 || *height <= 0.0);
+            match empty {
+                Some((_, _, width, height)) => Err(format!("{}x{} is empty", width, height)),
+                None => Ok(()),
+            }
+        });
+        checked("hitbox".to_string(), check)
+    });
+    let foot = clip
+        .foot
+        .as_ref()
+        .filter(|foot| checked("foot".to_string(), foot.check(specs.len())));
+
+    let mut frames = Vec::with_capacity(specs.len());
+    let mut durations = Vec::with_capacity(specs.len());
+    for (idx, (spec, duration)) in specs.iter().enumerate() {
+        let handle = match decoder.frame(dir, spec, clip.mirror) {
+            Ok(handle) => handle,
+            Err(error) => {
+                errors.push(error);
+                continue;
+            }
+        };
+        frames.push(handle);
+        // mirrored frames carry their points and boxes flipped along
+        let flip = |x: f32, width: f32| match clip.mirror {
+            true => 1.0 - x - width,
+            false => x,
+        };
+        for (anchor, points) in &mut metadata.anchors {
+            let (x, y) = clip.anchors[anchor].get(idx);
+            points.push((flip(x, 0.0), y));
+        }
+        if let Some(hitbox) = hitbox {
+            let (x, y, width, height) = hitbox.get(idx);
+            metadata.hitboxes.push((flip(x, width), y, width, height));
+        }
+        if let Some(foot) = foot {
+            let (x, y) = foot.get(idx);
+            metadata.feet.push((flip(x, 0.0), y));
+        }
+        durations.push(
+            clip.durations
+                .as_ref()
+                .and_then(|durations| durations.get(idx).copied())
+                .or(*duration)
+                .or(clip.frame_duration)
+                .unwrap_or(DEFAULT_FRAME_DURATION),
+        );
+    }
+
+    if clip.reverse != tag_reverse {
+        frames.reverse();
+        durations.reverse();
+        metadata
+            .anchors
+            .values_mut()
+            .for_each(|points| points.reverse());
+        metadata.hitboxes.reverse();
+        metadata.feet.reverse();
+    }
+
+    Clip::new(
+        frames,
+        durations,
+        clip.mode.unwrap_or(tag_mode),
+        sprite_size,
+    )
+    .with_metadata(metadata, clip.mirror)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::image::{codecs::gif::GifEncoder, Delay, Frame, Rgba, RgbaImage};
+    use std::{env, fs, path::PathBuf, process};
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+    const CLEAR: Rgba<u8> = Rgba([0, 0, 0, 0]);
+
+    // A character folder of its own for every test
+    fn folder(name: &str, files: &[(&str, &[u8])]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("wayland-penguins-{}-{}", name, process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (file, bytes) in files {
+            fs::write(dir.join(file), bytes).unwrap();
+        }
+        dir
+    }
+
+    fn png(image: &RgbaImage) -> Vec<u8> {
+        let mut png = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png), ::image::ImageFormat::Png)
+            .unwrap();
+        png
+    }
+
+    fn pixels(sprite: &Sprite) -> RgbaImage {
+        match sprite {
+            Sprite::Raster(image::Handle::Bytes(_, bytes)) => {
+                ::image::load_from_memory(bytes).unwrap().to_rgba8()
+            }
+            sprite => panic!("not an encoded raster frame: {:?}", sprite),
+        }
+    }
+
+    // Two frames on a 3x2 sheet: a full 2x2 one and a single red pixel
+    // trimmed from the top right corner of another 2x2 one
+    const EXPORT: &str = r#"{
+        "frames": [
+            {"frame": {"x": 0, "y": 0, "w": 2, "h": 2}, "duration": 32},
+            {
+                "frame": {"x": 2, "y": 0, "w": 1, "h": 1},
+                "trimmed": true,
+                "spriteSourceSize": {"x": 1, "y": 0, "w": 1, "h": 1},
+                "sourceSize": {"w": 2, "h": 2},
+                "duration": 64
+            }
+        ],
+        "meta": {
+            "image": "penguin.png",
+            "frameTags": [
+                {"name": "walk", "from": 0, "to": 1, "direction": "forward"},
+                {"name": "wave", "from": 1, "to": 1, "direction": "pingpong"}
+            ]
+        }
+    }"#;
+
+    fn aseprite_character(name: &str, manifest: &str) -> (Character, Vec<AssetError>) {
+        let mut sheet = RgbaImage::from_pixel(3, 2, Rgba([0, 0, 255, 255]));
+        sheet.put_pixel(2, 0, RED);
+        let dir = folder(
+            name,
+            &[
+                (MANIFEST_FILE, manifest.as_bytes()),
+                ("penguin.json", EXPORT.as_bytes()),
+                ("penguin.png", &png(&sheet)),
+            ],
+        );
+        let loaded = load_character(&AssetDir::Disk(dir.clone()));
+        fs::remove_dir_all(dir).unwrap();
+        loaded
+    }
+
+    #[test]
+    fn aseprite_tags_become_clips() {
+        let (character, errors) = aseprite_character(
+            "tags",
+            r#"
+            aseprite = ["penguin.json"]
+
+            [clips.wave]
+            aseprite = "penguin.json"
+            mode = "loop"
+            "#,
+        );
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        let mut names: Vec<&str> = character.clip_names().collect();
+        names.sort();
+        assert_eq!(names, ["walk", "wave"]);
+        assert_eq!(character.clip("walk").frames.len(), 2);
+        assert_eq!(character.clip("walk").mode, PlayMode::Loop);
+        // declared clips keep their own settings
+        assert_eq!(character.clip("wave").frames.len(), 1);
+        assert_eq!(character.clip("wave").mode, PlayMode::Loop);
+    }
+
+    #[test]
+    fn aseprite_tags_set_the_play_mode() {
+        let (character, errors) = aseprite_character("modes", r#"aseprite = ["penguin.json"]"#);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(character.clip("wave").mode, PlayMode::PingPong);
+    }
+
+    #[test]
+    fn aseprite_frames_keep_their_durations() {
+        let (character, _) = aseprite_character("durations", r#"aseprite = ["penguin.json"]"#);
+        let walk = character.clip("walk");
+        assert_eq!(walk.duration_ms(), 96);
+        let indices: Vec<usize> = (0..7).map(|tick| walk.frame_index(tick)).collect();
+        assert_eq!(indices, [0, 0, 1, 1, 1, 1, 0]);
+    }
+
+    #[test]
+    fn trimmed_frames_are_put_back_in_place() {
+        let (character, _) = aseprite_character("trimmed", r#"aseprite = ["penguin.json"]"#);
+        let frame = pixels(&character.clip("walk").frames[1]);
+        assert_eq!(frame.dimensions(), (2, 2));
+        assert_eq!(frame.get_pixel(1, 0), &RED);
+        for (x, y) in [(0, 0), (0, 1), (1, 1)] {
+            assert_eq!(frame.get_pixel(x, y), &CLEAR);
+        }
+    }
+
+    #[test]
+    fn animated_images_keep_their_delays() {
+        let mut gif = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut gif);
+            // GIF delays are in hundredths of a second
+            for (pixel, delay) in [(CLEAR, 40), (RED, 20)] {
+                let frame = RgbaImage::from_pixel(2, 2, pixel);
+                let delay = Delay::from_numer_denom_ms(delay, 1);
+                encoder
+                    .encode_frame(Frame::from_parts(frame, 0, 0, delay))
+                    .unwrap();
+            }
+        }
+        let manifest = "[clips.wave]\nanimation = \"wave.gif\"";
+        let dir = folder(
+            "animated",
+            &[(MANIFEST_FILE, manifest.as_bytes()), ("wave.gif", &gif)],
+        );
+        let (character, errors) = load_character(&AssetDir::Disk(dir.clone()));
+        fs::remove_dir_all(dir).unwrap();
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        let wave = character.clip("wave");
+        assert_eq!(wave.frames.len(), 2);
+        assert_eq!(wave.duration_ms(), 60);
+        let indices: Vec<usize> = (0..4).map(|tick| wave.frame_index(tick)).collect();
+        assert_eq!(indices, [0, 0, 0, 1]);
+        assert_eq!(pixels(&wave.frames[1]).get_pixel(0, 0), &RED);
+    }
+
+    #[test]
+    fn unreadable_image_folders_are_reported() {
+        let registry = AssetRegistry::collecting(AssetLocator::pack(env::temp_dir()));
+        let images = registry.loose_images("wayland-penguins-no-such-folder");
+        assert!(images.is_empty());
+        let errors = registry.take_errors();
+        assert!(
+            matches!(errors[..], [AssetError::DirectoryUnreadable { .. }]),
+            "{:?}",
+            errors
+        );
+    }
+
+    #[test]
+    fn balloon_variants_are_resolved_once() {
+        let pack = folder("balloons", &[]);
+        let balloons = pack.join(BALLOON_CHARACTER);
+        fs::create_dir_all(&balloons).unwrap();
+        let sprite = png(&RgbaImage::from_pixel(2, 2, RED));
+        // both claim the penguin carrying balloon of the same colour
+        for file in ["Red-BalloonWithPenguin.png", "red_balloon_with_penguin.png"] {
+            fs::write(balloons.join(file), &sprite).unwrap();
+        }
+
+        let registry = AssetRegistry::collecting(AssetLocator::pack(pack.clone()));
+        assert!(registry.balloon_variants().is_empty());
+        assert!(registry.balloon_variants().is_empty());
+        let errors = registry.take_errors();
+        fs::remove_dir_all(pack).unwrap();
+        assert!(
+            matches!(errors[..], [AssetError::RoleAmbiguous { .. }]),
+            "{:?}",
+            errors
+        );
+    }
+
+    #[test]
+    fn recoloured_frames_follow_their_source_frames() {
+        let pack = folder("recolor", &[]);
+        let palettes = pack.join(PALETTES_FOLDER);
+        fs::create_dir_all(&palettes).unwrap();
+        for (file, source) in [
+            (
+                "palettes.toml",
+                "source = \"from.hex\"\n[swaps.blue]\npalette = \"to.hex\"",
+            ),
+            ("from.hex", "ff0000"),
+            ("to.hex", "0000ff"),
+        ] {
+            fs::write(palettes.join(file), source).unwrap();
+        }
+        let registry = AssetRegistry::collecting(AssetLocator::pack(pack.clone()));
+        let clip = || Clip::placeholder((60.0, 60.0));
+
+        let first = clip();
+        let recolored = registry.recolor(first.clone(), Some("blue"));
+        let again = registry.recolor(first.clone(), Some("blue"));
+        // frames that look the same but are not the same get their own
+        let other = registry.recolor(clip(), Some("blue"));
+        fs::remove_dir_all(pack).unwrap();
+        assert!(registry.take_errors().is_empty());
+
+        assert!(!Rc::ptr_eq(&recolored.frames, &first.frames));
+        assert!(Rc::ptr_eq(&recolored.frames, &again.frames));
+        assert!(!Rc::ptr_eq(&recolored.frames, &other.frames));
+    }
+}
@@ -0,0 +1,93 @@
+use ::image::{imageops, RgbaImage};
+use serde::Deserialize;
+
+// Splits a sprite sheet into equally sized cells, read row by row
+//
+//   grid = { columns = 8, rows = 5 }
+//   grid = { columns = 8, rows = 5, margin = 1, spacing = 2 }
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SheetGrid {
+    pub columns: u32,
+    pub rows: u32,
+    // Pixels around the outside of the grid
+    #[serde(default)]
+    pub margin: u32,
+    // Pixels between neighbouring cells
+    #[serde(default)]
+    pub spacing: u32,
+}
+
+// One frame inside a sprite sheet; `duration` (milliseconds) is optional
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub struct FrameRect {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+    pub duration: Option<u32>,
+}
+
+impl SheetGrid {
+    pub fn rects(&self, sheet_width: u32, sheet_height: u32) -> Result<Vec<FrameRect>, String> {
+        if self.columns == 0 || self.rows == 0 {
+            return Err("grid needs at least one column and one row".to_string());
+        }
+
+        let usable_width =
+            sheet_width.saturating_sub(2 * self.margin + (self.columns - 1) * self.spacing);
+        let usable_height =
+            sheet_height.saturating_sub(2 * self.margin + (self.rows - 1) * self.spacing);
+        let (w, h) = (usable_width / self.columns, usable_height / self.rows);
+        if w == 0 || h == 0 {
+            return Err(format!(
+                "a {}x{} sheet is too small for a {}x{} grid",
+                sheet_width, sheet_height, self.columns, self.rows
+            ));
+        }
+
+        Ok((0..self.rows)
+            .flat_map(|row| (0..self.columns).map(move |column| (row, column)))
+            .map(|(row, column)| FrameRect {
+                x: self.margin + column * (w + self.spacing),
+                y: self.margin + row * (h + self.spacing),
+                w,
+                h,
+                duration: None,
+            })
+            .collect())
+    }
+}
+
+// Accepts either a bare list of rectangles or `{ "frames": [...] }`
+pub fn parse_rects(json: &[u8]) -> Result<Vec<FrameRect>, serde_json::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RectsFile {
+        List(Vec<FrameRect>),
+        Object { frames: Vec<FrameRect> },
+    }
+
+    Ok(match serde_json::from_slice(json)? {
+        RectsFile::List(frames) | RectsFile::Object { frames } => frames,
+    })
+}
+
+pub fn crop(sheet: &RgbaImage, rect: &FrameRect) -> Result<RgbaImage, String> {
+    if rect.w == 0
+        || rect.h == 0
+        || rect.x + rect.w > sheet.width()
+        || rect.y + rect.h > sheet.height()
+    {
+        return Err(format!(
+            "frame {}x{}+{}+{} lies outside the {}x{} sheet",
+            rect.w,
+            rect.h,
+            rect.x,
+            rect.y,
+            sheet.width(),
+            sheet.height()
+        ));
+    }
+    Ok(imageops::crop_imm(sheet, rect.x, rect.y, rect.w, rect.h).to_image())
+}
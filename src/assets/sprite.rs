@@ -0,0 +1,164 @@
+use super::recolor::Recolor;
+use ::image::RgbaImage;
+use iced::advanced::image as advanced_image;
+use iced::widget::image;
+use resvg::{tiny_skia, usvg};
+use std::{cell::RefCell, collections::HashMap, io::Cursor, rc::Rc};
+
+// Rasterised sizes kept per vector frame; beyond that the cache starts over
+const MAX_CACHED_SIZES: usize = 8;
+
+// One frame of a clip. Raster frames are drawn as they are, vector frames are
+// rasterised at the size they take up on screen, so they stay crisp at any
+// sprite size or output scale. Recoloured frames are built from another
+// frame the first time they are drawn.
+#[derive(Debug, Clone)]
+pub enum Sprite {
+    Raster(image::Handle),
+    Vector(Rc<VectorSprite>),
+    Recolored(Rc<RecoloredSprite>),
+}
+
+#[derive(Debug)]
+pub struct VectorSprite {
+    tree: usvg::Tree,
+    mirror: bool,
+    // rasterised frames keyed by their size in physical pixels
+    rasterised: RefCell<HashMap<(u32, u32), image::Handle>>,
+}
+
+impl Sprite {
+    // Image to draw into a `pixel_size` (physical pixels) rectangle
+    pub fn handle(&self, pixel_size: (u32, u32)) -> image::Handle {
+        match self {
+            Sprite::Raster(handle) => handle.clone(),
+            Sprite::Vector(vector) => vector.rasterise(pixel_size),
+            Sprite::Recolored(recolored) => recolored.handle(pixel_size),
+        }
+    }
+
+    pub fn recolored(&self, recolor: Rc<Recolor>) -> Sprite {
+        Sprite::Recolored(Rc::new(RecoloredSprite {
+            source: self.clone(),
+            recolor,
+            recolored: RefCell::new(HashMap::new()),
+        }))
+    }
+}
+
+#[derive(Debug)]
+pub struct RecoloredSprite {
+    source: Sprite,
+    recolor: Rc<Recolor>,
+    // recoloured images keyed by the id of the image they were made from
+    recolored: RefCell<HashMap<advanced_image::Id, image::Handle>>,
+}
+
+impl RecoloredSprite {
+    fn handle(&self, pixel_size: (u32, u32)) -> image::Handle {
+        let source = self.source.handle(pixel_size);
+        if let Some(handle) = self.recolored.borrow().get(&source.id()) {
+            return handle.clone();
+        }
+
+        let handle = match &source {
+            // kept encoded like the frames of the registry
+            image::Handle::Bytes(_, bytes) => match ::image::load_from_memory(bytes) {
+                Ok(decoded) => {
+                    let mut rgba = decoded.to_rgba8();
+                    self.recolor.image(&mut rgba);
+                    encode_png(&rgba)
+                }
+                // decoding problems show up as the original frame
+                Err(_) => source.clone(),
+            },
+            image::Handle::Rgba {
+                width,
+                height,
+                pixels,
+                ..
+            } => match RgbaImage::from_raw(*width, *height, pixels.to_vec()) {
+                Some(mut rgba) => {
+                    self.recolor.image(&mut rgba);
+                    image::Handle::from_rgba(*width, *height, rgba.into_raw())
+                }
+                None => source.clone(),
+            },
+            image::Handle::Path(..) => source.clone(),
+        };
+
+        let mut recolored = self.recolored.borrow_mut();
+        if recolored.len() >= MAX_CACHED_SIZES {
+            recolored.clear();
+        }
+        recolored.insert(source.id(), handle.clone());
+        handle
+    }
+}
+
+pub(super) fn encode_png(rgba: &RgbaImage) -> image::Handle {
+    let mut png = Vec::new();
+    rgba.write_to(&mut Cursor::new(&mut png), ::image::ImageFormat::Png)
+        .expect("encoding an RGBA image as PNG into memory cannot fail");
+    image::Handle::from_bytes(png)
+}
+
+impl VectorSprite {
+    // SVG text is not rendered, convert it to paths first
+    pub fn parse(svg: &[u8], mirror: bool) -> Result<Self, usvg::Error> {
+        Ok(Self {
+            tree: usvg::Tree::from_data(svg, &usvg::Options::default())?,
+            mirror,
+            rasterised: RefCell::new(HashMap::new()),
+        })
+    }
+
+    // Size the SVG declares for itself
+    pub fn size(&self) -> (f32, f32) {
+        let size = self.tree.size();
+        (size.width(), size.height())
+    }
+
+    fn rasterise(&self, (width, height): (u32, u32)) -> image::Handle {
+        let (width, height) = (width.max(1), height.max(1));
+        if let Some(handle) = self.rasterised.borrow().get(&(width, height)) {
+            return handle.clone();
+        }
+
+        let Some(mut pixmap) = tiny_skia::Pixmap::new(width, height) else {
+            return image::Handle::from_rgba(1, 1, vec![0; 4]);
+        };
+        let (svg_width, svg_height) = self.size();
+        let mut transform =
+            tiny_skia::Transform::from_scale(width as f32 / svg_width, height as f32 / svg_height);
+        if self.mirror {
+            transform = transform.post_concat(tiny_skia::Transform::from_row(
+                -1.0,
+                0.0,
+                0.0,
+                1.0,
+                width as f32,
+                0.0,
+            ));
+        }
+        resvg::render(&self.tree, transform, &mut pixmap.as_mut());
+
+        // tiny-skia keeps premultiplied alpha, iced expects straight alpha
+        let pixels: Vec<u8> = pixmap
+            .pixels()
+            .iter()
+            .flat_map(|pixel| {
+                let color = pixel.demultiply();
+                [color.red(), color.green(), color.blue(), color.alpha()]
+            })
+            .collect();
+        let handle = image::Handle::from_rgba(width, height, pixels);
+
+        let mut rasterised = self.rasterised.borrow_mut();
+        if rasterised.len() >= MAX_CACHED_SIZES {
+            rasterised.clear();
+        }
+        rasterised.insert((width, height), handle.clone());
+        handle
+    }
+}
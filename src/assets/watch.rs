@@ -0,0 +1,137 @@
+use crate::watch::{IDLE_MS, SETTLE_MS};
+use iced::futures::channel::mpsc::{self, UnboundedSender};
+use iced::Subscription;
+use nix::{
+    errno::Errno,
+    poll::{poll, PollFd, PollFlags},
+    sys::inotify::{AddWatchFlags, InitFlags, Inotify, WatchDescriptor},
+};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::read_dir,
+    os::fd::AsFd,
+    path::{Path, PathBuf},
+    thread,
+};
+
+// Names of the character folders (e.g. "Balloon Animation") whose files
+// changed below any directory of `search_path`. Directories that do not
+// exist yet are not watched; the built-in sprites never change.
+pub fn watch(search_path: &[PathBuf]) -> Subscription<Vec<String>> {
+    Subscription::run_with(search_path.to_vec(), |search_path| {
+        let (sender, receiver) = mpsc::unbounded();
+        let search_path = search_path.clone();
+        thread::spawn(move || {
+            if let Err(error) = watch_dirs(&search_path, &sender) {
+                tracing::warn!("Sprite hot-reload disabled: {}", error);
+            }
+        });
+        receiver
+    })
+}
+
+struct Watches {
+    inotify: Inotify,
+    // watched directory and the character folder it belongs to, None for a
+    // search path directory itself
+    dirs: HashMap<WatchDescriptor, (PathBuf, Option<String>)>,
+}
+
+impl Watches {
+    // Watches `dir` and every directory below it. Inside a search path
+    // directory, each sub directory is a character folder of its own.
+    fn add(&mut self, dir: &Path, folder: Option<String>) {
+        let mask = AddWatchFlags::IN_CLOSE_WRITE
+            | AddWatchFlags::IN_CREATE
+            | AddWatchFlags::IN_DELETE
+            | AddWatchFlags::IN_MOVED_FROM
+            | AddWatchFlags::IN_MOVED_TO;
+        let Ok(wd) = self.inotify.add_watch(dir, mask) else {
+            return;
+        };
+        self.dirs.insert(wd, (dir.to_path_buf(), folder.clone()));
+
+        for entry in read_dir(dir).into_iter().flatten().flatten() {
+            if entry.path().is_dir() {
+                let folder = folder
+                    .clone()
+                    .unwrap_or_else(|| entry.file_name().to_string_lossy().to_string());
+                self.add(&entry.path(), Some(folder));
+            }
+        }
+    }
+}
+
+fn watch_dirs(search_path: &[PathBuf], sender: &UnboundedSender<Vec<String>>) -> nix::Result<()> {
+    let mut watches = Watches {
+        inotify: Inotify::init(InitFlags::IN_CLOEXEC | InitFlags::IN_NONBLOCK)?,
+        dirs: HashMap::new(),
+    };
+    for dir in search_path {
+        watches.add(dir, None);
+    }
+    if watches.dirs.is_empty() {
+        return Ok(());
+    }
+
+    let mut changed = BTreeSet::new();
+    loop {
+        let timeout = if changed.is_empty() {
+            IDLE_MS
+        } else {
+            SETTLE_MS
+        };
+        let mut fds = [PollFd::new(watches.inotify.as_fd(), PollFlags::POLLIN)];
+        let ready = match poll(&mut fds, timeout) {
+            Ok(ready) => ready,
+            Err(Errno::EINTR) => continue,
+            Err(error) => return Err(error),
+        };
+
+        if ready == 0 {
+            if !changed.is_empty() {
+                let folders = std::mem::take(&mut changed).into_iter().collect();
+                if sender.unbounded_send(folders).is_err() {
+                    return Ok(());
+                }
+            } else if sender.is_closed() {
+                return Ok(());
+            }
+            continue;
+        }
+
+        let events = match watches.inotify.read_events() {
+            Ok(events) => events,
+            Err(Errno::EAGAIN | Errno::EINTR) => continue,
+            Err(error) => return Err(error),
+        };
+        for event in events {
+            if event.mask.contains(AddWatchFlags::IN_IGNORED) {
+                watches.dirs.remove(&event.wd);
+                continue;
+            }
+            let (Some((dir, folder)), Some(name)) = (watches.dirs.get(&event.wd), event.name)
+            else {
+                continue;
+            };
+
+            // only folders count at the top of a search path directory
+            if folder.is_none() && !event.mask.contains(AddWatchFlags::IN_ISDIR) {
+                continue;
+            }
+
+            let path = dir.join(&name);
+            let folder = folder
+                .clone()
+                .unwrap_or_else(|| name.to_string_lossy().to_string());
+            let new_dir = event.mask.contains(AddWatchFlags::IN_ISDIR)
+                && event
+                    .mask
+                    .intersects(AddWatchFlags::IN_CREATE | AddWatchFlags::IN_MOVED_TO);
+            if new_dir {
+                watches.add(&path, Some(folder.clone()));
+            }
+            changed.insert(folder);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use crate::animations::animation::Kind;
+use crate::config::SurfaceLayer;
+use crate::pixelate::Dither;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Penguins walking around on your Wayland desktop")]
+pub struct Cli {
+    /// Directory searched for sprite folders before $XDG_DATA_HOME and $XDG_DATA_DIRS
+    #[arg(long, global = true, value_name = "DIR")]
+    pub assets: Option<PathBuf>,
+
+    /// Config file [default: $XDG_CONFIG_HOME/wayland-penguins/config.toml]
+    #[arg(short, long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Messages at this level and above are logged [default: $RUST_LOG, otherwise info]
+    #[arg(long, global = true, value_enum, value_name = "LEVEL")]
+    pub log_level: Option<LogLevel>,
+
+    #[command(flatten)]
+    pub run: RunArgs,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Show the penguins; the same as giving no command
+    Run(RunArgs),
+    /// Send a command to the running instance and print its reply
+    Msg(MsgArgs),
+    /// List the characters, clips, palette swaps and accessories of the assets
+    ListAnimations,
+    /// Check the config file and print every problem; fails when it has any
+    ValidateConfig,
+    /// Convert frames to pixel art limited to a palette
+    Pixelate(PixelateArgs),
+    /// Check an asset pack and print a JSON report; fails when it has errors
+    ValidateAssets(ValidateAssetsArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct PixelateArgs {
+    /// Image or directory of images to convert
+    pub input: PathBuf,
+
+    /// Directory the converted frames are written to [default: next to an image, or a <PREFIX><DIR> folder beside a directory]
+    #[arg(short, long, value_name = "DIR")]
+    pub output: Option<PathBuf>,
+
+    /// Edge length in pixels of the blocks every frame is reduced to
+    #[arg(short, long, default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..))]
+    pub block_size: u32,
+
+    /// Palette file (.gpl, .hex, .txt or .json) [default: the palette of the shipped sprites]
+    #[arg(short, long, value_name = "FILE")]
+    pub palette: Option<PathBuf>,
+
+    #[arg(short, long, value_enum, default_value_t = Dither::None)]
+    pub dither: Dither,
+
+    /// Prepended to every output file name; inputs that already start with it are skipped
+    #[arg(long, default_value = "pixelated-")]
+    pub prefix: String,
+
+    /// Also convert the frames in sub-directories, keeping their layout
+    #[arg(short, long)]
+    pub recursive: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ValidateAssetsArgs {
+    /// Pack directory holding character folders, as passed to --assets
+    pub dir: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct MsgArgs {
+    /// Print the reply as the JSON line the instance sent
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    #[command(subcommand)]
+    pub command: MsgCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MsgCommand {
+    /// Add penguins, as many as the maximum population leaves room for
+    Spawn {
+        kind: Kind,
+        /// Penguins to add
+        #[arg(short = 'n', long, default_value_t = 1)]
+        count: usize,
+    },
+    /// Send off the newest penguins of a kind [default: all of them]
+    Despawn {
+        kind: Kind,
+        /// Penguins to send off
+        #[arg(short = 'n', long)]
+        count: Option<usize>,
+    },
+    /// Stop every penguin where it is
+    Pause,
+    /// Let the paused penguins carry on
+    Resume,
+    /// Multiply the speed of a kind, or of all of them, until the config is reloaded
+    Speed {
+        multiplier: f32,
+        /// Kind of penguin the multiplier is for [default: all of them]
+        #[arg(long)]
+        kind: Option<Kind>,
+    },
+    /// Open the menu
+    ShowMenu,
+    /// Close the menu
+    HideMenu,
+    /// List the penguins and what they are doing
+    State,
+    /// Read the config file again
+    ReloadConfig,
+}
+
+// Options of the overlay, given with or without `run`
+#[derive(Debug, Clone, Args)]
+pub struct RunArgs {
+    /// Layer of the overlay surface [default: from the config, otherwise top]
+    #[arg(long, value_enum)]
+    pub layer: Option<SurfaceLayer>,
+
+    /// Output (e.g. DP-1) the overlay is shown on, repeat for more [default: from the config, otherwise all]
+    #[arg(long = "output", value_name = "NAME")]
+    pub outputs: Vec<String>,
+
+    /// Walkers every spawn wave brings [default: from the config]
+    #[arg(long, value_name = "COUNT")]
+    pub walkers: Option<u32>,
+
+    /// Penguins arriving by balloon every spawn wave [default: from the config]
+    #[arg(long, value_name = "COUNT")]
+    pub balloons: Option<u32>,
+
+    /// Copters every spawn wave brings [default: from the config]
+    #[arg(long, value_name = "COUNT")]
+    pub copters: Option<u32>,
+
+    /// Seed of every random choice, to get the same penguins on every run
+    #[arg(long)]
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
@@ -0,0 +1,403 @@
+pub mod watch;
+
+use crate::animations::event::EventKind;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+const APP_DIR: &str = "wayland-penguins";
+pub const CONFIG_FILE: &str = "config.toml";
+
+// Tuning read from $XDG_CONFIG_HOME/wayland-penguins/config.toml. Every key
+// is optional, the defaults are the built-in behaviour:
+//
+//   # multiplies the sprite size of every character
+//   sprite_scale = 1.0
+//   # outputs the penguins are shown on by name, every output when empty
+//   outputs = ["DP-1", "HDMI-A-1"]
+//   # "wrap": penguins walk and drift over to the output next to theirs,
+//   # "independent": every output keeps its penguins to itself
+//   output_layout = "wrap"
+//
+//   # the layer surface the penguins are drawn on, on every output
+//   [surface]
+//   # background, bottom, top or overlay
+//   layer = "top"
+//   # edges of the output it sticks to
+//   anchor = ["top", "bottom", "left", "right"]
+//   # 0 stretches it between the anchors, which needs both anchors of
+//   # that direction
+//   width = 0
+//   height = 0
+//   # space to the anchored edges: top, right, bottom, left
+//   margin = [0, 0, 0, 0]
+//   # stays out of the space panels like a bottom bar reserve
+//   respect_panels = false
+//   # the ground everybody walks and lands on, above the bottom edge
+//   ground_offset = 0
+//
+//   [spawn]
+//   # penguins every wave brings, by how they arrive
+//   walkers = 0
+//   balloons = 1
+//   copters = 1
+//   # waves in total, the first one at startup, then one every interval
+//   waves = 6
+//   interval_ms = 3200
+//   # waves stop adding penguins once this many walk or fly around
+//   max_population = 32
+//
+//   [speed]
+//   walk = 1.0
+//   balloon = 1.0
+//   copter = 1.0
+//
+//   # shell commands run when the penguins do something, see `HooksConfig`
+//   [hooks]
+//   landed = "paplay ~/sounds/pop.oga"
+//   population-full = "notify-send Penguins 'No room for more'"
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub sprite_scale: f32,
+    pub outputs: Vec<String>,
+    pub output_layout: OutputLayout,
+    pub surface: SurfaceConfig,
+    pub spawn: SpawnConfig,
+    pub speed: SpeedConfig,
+    pub hooks: HooksConfig,
+}
+
+// How the outputs the penguins are shown on relate to each other
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputLayout {
+    // one world laid out like the compositor lays out the outputs
+    #[default]
+    Wrap,
+    Independent,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SurfaceConfig {
+    pub layer: SurfaceLayer,
+    pub anchor: Vec<Edge>,
+    pub width: u32,
+    pub height: u32,
+    pub margin: [i32; 4],
+    pub respect_panels: bool,
+    pub ground_offset: f32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SurfaceLayer {
+    Background,
+    Bottom,
+    #[default]
+    Top,
+    Overlay,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Edge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl SurfaceConfig {
+    pub fn anchored(&self, edge: Edge) -> bool {
+        self.anchor.contains(&edge)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpawnConfig {
+    // start on the ground
+    pub walkers: u32,
+    // float down in a balloon and step out
+    pub balloons: u32,
+    // fly down, hover and leave again
+    pub copters: u32,
+    pub waves: u32,
+    pub interval_ms: u32,
+    pub max_population: u32,
+}
+
+// Multipliers of the built-in speeds
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpeedConfig {
+    pub walk: f32,
+    pub balloon: f32,
+    pub copter: f32,
+}
+
+// Commands run with `sh -c` on every event of their name, without waiting
+// for them. They find the event in $PENGUIN_EVENT, the penguin in
+// $PENGUIN_KIND, $PENGUIN_X and $PENGUIN_Y (where its foot is), the output
+// in $PENGUIN_OUTPUT and how many penguins there are on it in
+// $PENGUIN_POPULATION.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct HooksConfig {
+    pub spawned: Option<String>,
+    // a balloon or copter touched the ground
+    pub landed: Option<String>,
+    // a copter lifts off again
+    pub took_off: Option<String>,
+    // a walker faces right again after walking left
+    pub turned_around: Option<String>,
+    pub clicked: Option<String>,
+    // a penguin left the screen or faded out
+    pub despawned: Option<String>,
+    // a spawn reached the maximum population
+    pub population_full: Option<String>,
+}
+
+impl HooksConfig {
+    pub fn command(&self, event: EventKind) -> Option<&str> {
+        match event {
+            EventKind::Spawned => self.spawned.as_deref(),
+            EventKind::Landed => self.landed.as_deref(),
+            EventKind::TookOff => self.took_off.as_deref(),
+            EventKind::TurnedAround => self.turned_around.as_deref(),
+            EventKind::Clicked => self.clicked.as_deref(),
+            EventKind::Despawned => self.despawned.as_deref(),
+            EventKind::PopulationFull => self.population_full.as_deref(),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            sprite_scale: 1.0,
+            outputs: Vec::new(),
+            output_layout: OutputLayout::default(),
+            surface: SurfaceConfig::default(),
+            spawn: SpawnConfig::default(),
+            speed: SpeedConfig::default(),
+            hooks: HooksConfig::default(),
+        }
+    }
+}
+
+impl Default for SurfaceConfig {
+    fn default() -> Self {
+        Self {
+            layer: SurfaceLayer::default(),
+            anchor: vec![Edge::Top, Edge::Bottom, Edge::Left, Edge::Right],
+            width: 0,
+            height: 0,
+            margin: [0; 4],
+            respect_panels: false,
+            ground_offset: 0.0,
+        }
+    }
+}
+
+impl Default for SpawnConfig {
+    fn default() -> Self {
+        Self {
+            walkers: 0,
+            balloons: 1,
+            copters: 1,
+            waves: 6,
+            interval_ms: 3200,
+            max_population: 32,
+        }
+    }
+}
+
+impl Default for SpeedConfig {
+    fn default() -> Self {
+        Self {
+            walk: 1.0,
+            balloon: 1.0,
+            copter: 1.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Unreadable {
+        path: String,
+        source: io::Error,
+    },
+    Invalid {
+        path: String,
+        source: toml::de::Error,
+    },
+    ValueInvalid {
+        path: String,
+        key: &'static str,
+        reason: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Unreadable { path, source } => {
+                write!(f, "{}: cannot read the config: {}", path, source)
+            }
+            ConfigError::Invalid { path, source } => write!(f, "{}: {}", path, source),
+            ConfigError::ValueInvalid { path, key, reason } => {
+                write!(f, "{}: `{}` {}", path, key, reason)
+            }
+        }
+    }
+}
+
+impl Config {
+    // The config at `explicit`, otherwise the default config file or the
+    // defaults when there is none. Every problem is returned, not just the
+    // first one.
+    pub fn load(explicit: Option<&Path>) -> Result<Self, Vec<ConfigError>> {
+        let Some(path) = config_path(explicit) else {
+            return Ok(Self::default());
+        };
+        let display = path.display().to_string();
+        let source = match fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(error) if error.kind() == io::ErrorKind::NotFound && explicit.is_none() => {
+                return Ok(Self::default())
+            }
+            Err(source) => {
+                return Err(vec![ConfigError::Unreadable {
+                    path: display,
+                    source,
+                }])
+            }
+        };
+        let config: Self = toml::from_str(&source).map_err(|source| {
+            vec![ConfigError::Invalid {
+                path: display.clone(),
+                source,
+            }]
+        })?;
+
+        let errors = config.check(&display);
+        match errors.is_empty() {
+            true => Ok(config),
+            false => Err(errors),
+        }
+    }
+
+    fn check(&self, path: &str) -> Vec<ConfigError> {
+        let positive = [
+            ("sprite_scale", self.sprite_scale),
+            ("speed.walk", self.speed.walk),
+            ("speed.balloon", self.speed.balloon),
+            ("speed.copter", self.speed.copter),
+        ];
+        let mut errors: Vec<ConfigError> = positive
+            .into_iter()
+            .filter(|(_, value)| !(value.is_finite() && *value > 0.0))
+            .map(|(key, value)| ConfigError::ValueInvalid {
+                path: path.to_string(),
+                key,
+                reason: format!("must be a number above 0, not {}", value),
+            })
+            .collect();
+        if self.spawn.interval_ms == 0 {
+            errors.push(ConfigError::ValueInvalid {
+                path: path.to_string(),
+                key: "spawn.interval_ms",
+                reason: "must be above 0".to_string(),
+            });
+        }
+        let surface = &self.surface;
+        let stretched = [
+            ("surface.width", surface.width, Edge::Left, Edge::Right),
+            ("surface.height", surface.height, Edge::Top, Edge::Bottom),
+        ];
+        for (key, size, start, end) in stretched {
+            if size == 0 && !(surface.anchored(start) && surface.anchored(end)) {
+                errors.push(ConfigError::ValueInvalid {
+                    path: path.to_string(),
+                    key,
+                    reason: "must be above 0 unless the surface is anchored on both sides"
+                        .to_string(),
+                });
+            }
+        }
+        if !(surface.ground_offset.is_finite() && surface.ground_offset >= 0.0) {
+            errors.push(ConfigError::ValueInvalid {
+                path: path.to_string(),
+                key: "surface.ground_offset",
+                reason: format!(
+                    "must be a number of 0 or above, not {}",
+                    surface.ground_offset
+                ),
+            });
+        }
+        errors
+    }
+
+    // Whether the penguins are shown on the output called `name`
+    pub fn shows_on(&self, name: &str) -> bool {
+        self.outputs.is_empty() || self.outputs.iter().any(|output| output == name)
+    }
+}
+
+// Where the config comes from: the file, `--config` or the default one, and
+// the layer, outputs and spawn counts given on the command line, which stay
+// in force whenever the file is read again
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSource {
+    pub explicit: Option<PathBuf>,
+    pub layer: Option<SurfaceLayer>,
+    pub outputs: Vec<String>,
+    pub walkers: Option<u32>,
+    pub balloons: Option<u32>,
+    pub copters: Option<u32>,
+}
+
+impl ConfigSource {
+    pub fn load(&self) -> Result<Config, Vec<ConfigError>> {
+        let mut config = Config::load(self.explicit.as_deref())?;
+        let spawn = &mut config.spawn;
+        spawn.walkers = self.walkers.unwrap_or(spawn.walkers);
+        spawn.balloons = self.balloons.unwrap_or(spawn.balloons);
+        spawn.copters = self.copters.unwrap_or(spawn.copters);
+        if !self.outputs.is_empty() {
+            config.outputs = self.outputs.clone();
+        }
+        config.surface.layer = self.layer.unwrap_or(config.surface.layer);
+        Ok(config)
+    }
+
+    // The file that is read, when there is one
+    pub fn path(&self) -> Option<PathBuf> {
+        config_path(self.explicit.as_deref())
+    }
+}
+
+pub fn report(errors: &[ConfigError]) {
+    for error in errors {
+        eprintln!("Config error: {}", error);
+    }
+}
+
+// `explicit`, otherwise $XDG_CONFIG_HOME/wayland-penguins/config.toml
+pub fn config_path(explicit: Option<&Path>) -> Option<PathBuf> {
+    if let Some(path) = explicit {
+        return Some(path.to_path_buf());
+    }
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join(APP_DIR).join(CONFIG_FILE))
+}
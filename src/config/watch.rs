@@ -0,0 +1,116 @@
+use crate::watch::{IDLE_MS, SETTLE_MS};
+use iced::futures::channel::mpsc::{self, UnboundedSender};
+use iced::Subscription;
+use nix::{
+    errno::Errno,
+    poll::{poll, PollFd, PollFlags},
+    sys::{
+        inotify::{AddWatchFlags, InitFlags, Inotify},
+        signal::{SigSet, Signal},
+        signalfd::{SfdFlags, SignalFd},
+    },
+};
+use std::{
+    os::fd::AsFd,
+    path::{Path, PathBuf},
+    thread,
+};
+
+// SIGHUP is read from a signalfd, which only works while no thread takes
+// the signal itself. Call before any other thread starts.
+pub fn block_hangup() -> nix::Result<()> {
+    hangup().thread_block()
+}
+
+fn hangup() -> SigSet {
+    let mut signals = SigSet::empty();
+    signals.add(Signal::SIGHUP);
+    signals
+}
+
+// Fires when the config file at `config` changed or the process got SIGHUP
+pub fn watch(config: Option<PathBuf>) -> Subscription<()> {
+    Subscription::run_with(config, |config| {
+        let (sender, receiver) = mpsc::unbounded();
+        let config = config.clone();
+        thread::spawn(move || {
+            if let Err(error) = watch_config(config.as_deref(), &sender) {
+                tracing::warn!("Config reload disabled: {}", error);
+            }
+        });
+        receiver
+    })
+}
+
+fn watch_config(config: Option<&Path>, sender: &UnboundedSender<()>) -> nix::Result<()> {
+    let signals = SignalFd::with_flags(&hangup(), SfdFlags::SFD_CLOEXEC | SfdFlags::SFD_NONBLOCK)?;
+    let inotify = Inotify::init(InitFlags::IN_CLOEXEC | InitFlags::IN_NONBLOCK)?;
+
+    // editors replace the file on save, so its folder is watched
+    let file_name = config.and_then(Path::file_name);
+    if let Some(config) = config {
+        let dir = match config.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let mask = AddWatchFlags::IN_CLOSE_WRITE
+            | AddWatchFlags::IN_CREATE
+            | AddWatchFlags::IN_DELETE
+            | AddWatchFlags::IN_MOVED_FROM
+            | AddWatchFlags::IN_MOVED_TO;
+        if inotify.add_watch(dir, mask).is_err() {
+            tracing::info!(
+                "{} is not watched, SIGHUP reloads the config",
+                dir.display()
+            );
+        }
+    }
+
+    let mut changed = false;
+    loop {
+        let timeout = match changed {
+            true => SETTLE_MS,
+            false => IDLE_MS,
+        };
+        let mut fds = [
+            PollFd::new(inotify.as_fd(), PollFlags::POLLIN),
+            PollFd::new(signals.as_fd(), PollFlags::POLLIN),
+        ];
+        let ready = match poll(&mut fds, timeout) {
+            Ok(ready) => ready,
+            Err(Errno::EINTR) => continue,
+            Err(error) => return Err(error),
+        };
+
+        if ready == 0 {
+            if changed {
+                changed = false;
+                if sender.unbounded_send(()).is_err() {
+                    return Ok(());
+                }
+            } else if sender.is_closed() {
+                return Ok(());
+            }
+            continue;
+        }
+
+        // a hangup reloads right away
+        let mut hung_up = false;
+        while let Ok(Some(_)) = signals.read_signal() {
+            hung_up = true;
+        }
+        if hung_up && sender.unbounded_send(()).is_err() {
+            return Ok(());
+        }
+
+        match inotify.read_events() {
+            Ok(events) => {
+                changed |= events
+                    .iter()
+                    .any(|event| event.name.is_some() && event.name.as_deref() == file_name);
+            }
+            Err(Errno::EAGAIN | Errno::EINTR) => {}
+            Err(error) => return Err(error),
+        }
+    }
+}
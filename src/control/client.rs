@@ -0,0 +1,108 @@
+use super::{socket_path, Request, Response};
+use crate::animations::animation::Kind;
+use crate::cli::{MsgArgs, MsgCommand};
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::Path,
+    process::ExitCode,
+};
+
+// Forwards the command to the running instance and prints its reply;
+// fails when no instance runs or the instance turned the command down
+pub fn run(args: &MsgArgs) -> ExitCode {
+    let Some(path) = socket_path() else {
+        eprintln!("No running instance: $XDG_RUNTIME_DIR is not set");
+        return ExitCode::FAILURE;
+    };
+    let line = match send(&path, &request(&args.command)) {
+        Ok(line) => line,
+        Err(error) => {
+            eprintln!("No running instance on {}: {}", path.display(), error);
+            return ExitCode::FAILURE;
+        }
+    };
+    let response: Response = match serde_json::from_str(&line) {
+        Ok(response) => response,
+        Err(error) => {
+            eprintln!("Unreadable reply {:?}: {}", line.trim_end(), error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match args.json {
+        true => print!("{}", line),
+        false => print_response(&args.command, &response),
+    }
+    match response.ok {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
+
+fn request(command: &MsgCommand) -> Request {
+    match *command {
+        MsgCommand::Spawn { kind, count } => Request::Spawn { kind, count },
+        MsgCommand::Despawn { kind, count } => Request::Despawn { kind, count },
+        MsgCommand::Pause => Request::Pause,
+        MsgCommand::Resume => Request::Resume,
+        MsgCommand::Speed { multiplier, kind } => Request::Speed { kind, multiplier },
+        MsgCommand::ShowMenu => Request::ShowMenu,
+        MsgCommand::HideMenu => Request::HideMenu,
+        MsgCommand::State => Request::List,
+        MsgCommand::ReloadConfig => Request::ReloadConfig,
+    }
+}
+
+// One request, one reply line
+fn send(path: &Path, request: &Request) -> io::Result<String> {
+    let stream = UnixStream::connect(path)?;
+    writeln!(&stream, "{}", serde_json::to_string(request)?)?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    match line.is_empty() {
+        true => Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "hung up without a reply",
+        )),
+        false => Ok(line),
+    }
+}
+
+fn print_response(command: &MsgCommand, response: &Response) {
+    if let Some(error) = &response.error {
+        eprintln!("Error: {}", error);
+        return;
+    }
+    match command {
+        MsgCommand::Spawn { kind, .. } => {
+            println!("Spawned {}", penguins(response.count, *kind))
+        }
+        MsgCommand::Despawn { kind, .. } => {
+            println!("Sent off {}", penguins(response.count, *kind))
+        }
+        MsgCommand::State => {
+            if response.paused == Some(true) {
+                println!("Paused");
+            }
+            for entity in response.entities.iter().flatten() {
+                println!(
+                    "{:<8} {:<16} {:>6.0} {:>6.0}",
+                    entity.kind.to_string(),
+                    entity.state,
+                    entity.x,
+                    entity.y
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+fn penguins(count: Option<usize>, kind: Kind) -> String {
+    let count = count.unwrap_or(0);
+    match count {
+        1 => format!("1 {}", kind),
+        _ => format!("{} {}s", count, kind),
+    }
+}
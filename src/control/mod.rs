@@ -0,0 +1,196 @@
+// The control socket lets compositor keybindings and scripts drive a
+// running instance. Clients write one JSON request per line and read one
+// JSON response line back:
+//
+//   {"command": "spawn", "kind": "balloon", "count": 2}
+//   {"command": "despawn", "kind": "copter"}
+//   {"command": "pause"}
+//   {"command": "resume"}
+//   {"command": "speed", "kind": "walker", "multiplier": 1.5}
+//   {"command": "show-menu"}
+//   {"command": "hide-menu"}
+//   {"command": "list"}
+//   {"command": "reload-config"}
+//
+//   {"ok": true, "count": 2}
+//   {"ok": false, "error": "..."}
+//
+// `kind` is one of walker, balloon or copter. `despawn` without a count
+// sends off every penguin of the kind, `speed` without a kind sets all of
+// them.
+pub mod client;
+pub mod socket;
+
+use crate::animations::animation::{Entity, Kind};
+use nix::unistd::getuid;
+use serde::{Deserialize, Serialize};
+use std::{env, path::PathBuf, sync::mpsc::Sender};
+
+pub const SOCKET_FILE: &str = "wayland-penguins.sock";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum Request {
+    Spawn {
+        kind: Kind,
+        #[serde(default = "one")]
+        count: usize,
+    },
+    Despawn {
+        kind: Kind,
+        count: Option<usize>,
+    },
+    Pause,
+    Resume,
+    Speed {
+        kind: Option<Kind>,
+        multiplier: f32,
+    },
+    ShowMenu,
+    HideMenu,
+    List,
+    ReloadConfig,
+}
+
+fn one() -> usize {
+    1
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    // penguins a spawn or despawn added or sent off
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paused: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entities: Option<Vec<Entity>>,
+}
+
+impl Response {
+    pub fn ok() -> Self {
+        Self {
+            ok: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn error(error: impl Into<String>) -> Self {
+        Self {
+            error: Some(error.into()),
+            ..Self::default()
+        }
+    }
+}
+
+// Carries the response back to the connection a request came in on
+#[derive(Debug, Clone)]
+pub struct Reply(Sender<Response>);
+
+impl Reply {
+    // The client may have hung up already, nobody is left to tell then
+    pub fn send(self, response: Response) {
+        let _ = self.0.send(response);
+    }
+}
+
+// $XDG_RUNTIME_DIR/wayland-penguins.sock, without a socket when the
+// runtime directory is not set
+pub fn socket_path() -> Option<PathBuf> {
+    env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .map(|dir| dir.join(SOCKET_FILE))
+}
+
+// Where the lock file goes when there is no socket to keep a second
+// instance out. The temporary directory is shared between users, hence the
+// uid in the name.
+pub fn lock_path() -> PathBuf {
+    env::temp_dir().join(format!("wayland-penguins-{}.lock", getuid()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Request {
+        serde_json::from_str(line).unwrap()
+    }
+
+    #[test]
+    fn spawn_defaults_to_one() {
+        assert!(matches!(
+            parse(r#"{"command": "spawn", "kind": "balloon"}"#),
+            Request::Spawn {
+                kind: Kind::Balloon,
+                count: 1
+            }
+        ));
+        assert!(matches!(
+            parse(r#"{"command": "spawn", "kind": "copter", "count": 3}"#),
+            Request::Spawn {
+                kind: Kind::Copter,
+                count: 3
+            }
+        ));
+    }
+
+    #[test]
+    fn optional_fields_may_be_left_out() {
+        assert!(matches!(
+            parse(r#"{"command": "despawn", "kind": "walker"}"#),
+            Request::Despawn {
+                kind: Kind::Walker,
+                count: None
+            }
+        ));
+        assert!(matches!(
+            parse(r#"{"command": "speed", "multiplier": 1.5}"#),
+            Request::Speed {
+                kind: None,
+                multiplier: 1.5
+            }
+        ));
+    }
+
+    #[test]
+    fn commands_are_kebab_case() {
+        assert!(matches!(
+            parse(r#"{"command": "show-menu"}"#),
+            Request::ShowMenu
+        ));
+        assert!(matches!(
+            parse(r#"{"command": "reload-config"}"#),
+            Request::ReloadConfig
+        ));
+        assert_eq!(
+            serde_json::to_string(&Request::HideMenu).unwrap(),
+            r#"{"command":"hide-menu"}"#
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_commands_and_kinds() {
+        assert!(serde_json::from_str::<Request>(r#"{"command": "fly"}"#).is_err());
+        assert!(
+            serde_json::from_str::<Request>(r#"{"command": "spawn", "kind": "seal"}"#).is_err()
+        );
+        assert!(serde_json::from_str::<Request>(r#"{"kind": "walker"}"#).is_err());
+    }
+
+    #[test]
+    fn responses_leave_out_unset_fields() {
+        assert_eq!(
+            serde_json::to_string(&Response::ok()).unwrap(),
+            r#"{"ok":true}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Response::error("no such penguin")).unwrap(),
+            r#"{"ok":false,"error":"no such penguin"}"#
+        );
+    }
+}
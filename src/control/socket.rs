@@ -0,0 +1,236 @@
+use super::{Reply, Request, Response};
+use crate::watch::IDLE_MS;
+use iced::futures::channel::mpsc::{self, UnboundedSender};
+use iced::Subscription;
+use nix::{
+    errno::Errno,
+    poll::{poll, PollFd, PollFlags},
+};
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions, TryLockError},
+    hash::{Hash, Hasher},
+    io::{self, BufRead, BufReader, Write},
+    os::{
+        fd::AsFd,
+        unix::net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::{mpsc as std_mpsc, Arc},
+    thread,
+    time::Duration,
+};
+
+// How long a connection waits for the app to answer a request
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+// The bound control socket, shared with the thread serving it
+#[derive(Debug, Clone)]
+pub struct Listener {
+    pub path: PathBuf,
+    listener: Arc<UnixListener>,
+}
+
+// Subscriptions tell listeners apart by their socket file
+impl Hash for Listener {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.path.hash(state);
+    }
+}
+
+impl Listener {
+    // Removes the socket file once the app stops answering on it
+    pub fn remove(&self) {
+        if let Err(error) = fs::remove_file(&self.path) {
+            tracing::warn!("Cannot remove {}: {}", self.path.display(), error);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum BindError {
+    // another instance answers on the socket
+    Running(PathBuf),
+    // another instance holds the lock file
+    Locked(PathBuf),
+    Io(PathBuf, io::Error),
+}
+
+impl fmt::Display for BindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Running(path) => write!(f, "another instance listens on {}", path.display()),
+            Self::Locked(path) => write!(f, "another instance holds {}", path.display()),
+            Self::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+        }
+    }
+}
+
+// Binding the socket is what makes an instance the only one. A socket file
+// nobody answers on is left over from an instance that did not shut down
+// cleanly and is replaced.
+pub fn bind(path: &Path) -> Result<Listener, BindError> {
+    let listener = match UnixListener::bind(path) {
+        Err(error) if error.kind() == io::ErrorKind::AddrInUse => {
+            if UnixStream::connect(path).is_ok() {
+                return Err(BindError::Running(path.to_path_buf()));
+            }
+            fs::remove_file(path).and_then(|()| UnixListener::bind(path))
+        }
+        result => result,
+    }
+    .map_err(|error| BindError::Io(path.to_path_buf(), error))?;
+
+    Ok(Listener {
+        path: path.to_path_buf(),
+        listener: Arc::new(listener),
+    })
+}
+
+// Without a control socket an exclusive lock on a file keeps instances
+// apart instead. The lock goes away with the returned file, or with the
+// process, so the file itself is left in place.
+pub fn lock(path: &Path) -> Result<File, BindError> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+        .map_err(|error| BindError::Io(path.to_path_buf(), error))?;
+    match file.try_lock() {
+        Ok(()) => Ok(file),
+        Err(TryLockError::WouldBlock) => Err(BindError::Locked(path.to_path_buf())),
+        Err(TryLockError::Error(error)) => Err(BindError::Io(path.to_path_buf(), error)),
+    }
+}
+
+// Requests coming in on the control socket, each with the way back to its
+// client
+pub fn listen(listener: Option<Listener>) -> Subscription<(Request, Reply)> {
+    Subscription::run_with(listener, |listener| {
+        let (sender, receiver) = mpsc::unbounded();
+        if let Some(listener) = listener.clone() {
+            thread::spawn(move || serve(&listener.listener, &sender));
+        }
+        receiver
+    })
+}
+
+// Polls instead of blocking in accept, so the thread also notices the
+// subscription going away while no client connects
+fn serve(listener: &UnixListener, sender: &UnboundedSender<(Request, Reply)>) {
+    if let Err(error) = listener.set_nonblocking(true) {
+        tracing::warn!("Control socket disabled: {}", error);
+        return;
+    }
+    loop {
+        if sender.is_closed() {
+            return;
+        }
+        let mut fds = [PollFd::new(listener.as_fd(), PollFlags::POLLIN)];
+        match poll(&mut fds, IDLE_MS) {
+            Ok(0) | Err(Errno::EINTR) => continue,
+            Ok(_) => {}
+            Err(error) => {
+                tracing::warn!("Control socket disabled: {}", error);
+                return;
+            }
+        }
+        match listener.accept() {
+            // the handler reads each line blocking
+            Ok((stream, _)) => match stream.set_nonblocking(false) {
+                Ok(()) => {
+                    let sender = sender.clone();
+                    thread::spawn(move || handle(stream, &sender));
+                }
+                Err(error) => tracing::warn!("Control connection failed: {}", error),
+            },
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => {}
+            Err(error) => tracing::warn!("Control connection failed: {}", error),
+        }
+    }
+}
+
+// Answers the requests of one client, one line each, until it hangs up
+fn handle(stream: UnixStream, sender: &UnboundedSender<(Request, Reply)>) {
+    let mut writer = &stream;
+    for line in BufReader::new(&stream).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let (reply, answer) = std_mpsc::channel();
+                if sender.unbounded_send((request, Reply(reply))).is_err() {
+                    return;
+                }
+                answer
+                    .recv_timeout(REPLY_TIMEOUT)
+                    .unwrap_or_else(|_| Response::error("the app did not answer"))
+            }
+            Err(error) => Response::error(format!("invalid request: {}", error)),
+        };
+        let Ok(response) = serde_json::to_string(&response) else {
+            return;
+        };
+        if writeln!(writer, "{}", response).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::futures::{executor::block_on, StreamExt};
+    use std::{env, process, sync::mpsc::RecvTimeoutError};
+
+    fn socket(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("wayland-penguins-{}-{}.sock", name, process::id()))
+    }
+
+    #[test]
+    fn serve_stops_without_a_connection() {
+        let path = socket("stop");
+        let listener = bind(&path).unwrap();
+        let (sender, receiver) = mpsc::unbounded();
+        let (done, stopped) = std_mpsc::channel();
+        let serving = listener.clone();
+        thread::spawn(move || {
+            serve(&serving.listener, &sender);
+            done.send(()).unwrap();
+        });
+
+        assert_eq!(
+            stopped.recv_timeout(Duration::from_millis(200)),
+            Err(RecvTimeoutError::Timeout)
+        );
+        drop(receiver);
+        let stop = Duration::from_millis(u64::from(IDLE_MS) * 2);
+        assert_eq!(stopped.recv_timeout(stop), Ok(()));
+        listener.remove();
+    }
+
+    #[test]
+    fn serve_answers_a_request() {
+        let path = socket("answer");
+        let listener = bind(&path).unwrap();
+        let (sender, mut receiver) = mpsc::unbounded();
+        let serving = listener.clone();
+        thread::spawn(move || serve(&serving.listener, &sender));
+
+        let mut client = UnixStream::connect(&path).unwrap();
+        writeln!(client, r#"{{"command": "pause"}}"#).unwrap();
+        let (request, reply) = block_on(receiver.next()).unwrap();
+        assert!(matches!(request, Request::Pause));
+        reply.send(Response::ok());
+
+        let mut line = String::new();
+        BufReader::new(&client).read_line(&mut line).unwrap();
+        assert_eq!(line.trim(), r#"{"ok":true}"#);
+        listener.remove();
+    }
+}
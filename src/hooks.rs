@@ -0,0 +1,42 @@
+use crate::animations::event::Event;
+use crate::config::HooksConfig;
+use std::{
+    process::{Command, Stdio},
+    thread,
+};
+
+// Starts the hook of `event` and lets it run; a thread waits for it to
+// report a failure
+pub fn run(hooks: &HooksConfig, event: &Event, output: &str, population: usize) {
+    let Some(command) = hooks.command(event.kind) else {
+        return;
+    };
+    let mut shell = Command::new("sh");
+    shell
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .env("PENGUIN_EVENT", event.kind.to_string())
+        .env("PENGUIN_OUTPUT", output)
+        .env("PENGUIN_POPULATION", population.to_string());
+    if let Some((kind, foot)) = event.penguin {
+        shell
+            .env("PENGUIN_KIND", kind.to_string())
+            .env("PENGUIN_X", format!("{:.0}", foot.x))
+            .env("PENGUIN_Y", format!("{:.0}", foot.y));
+    }
+
+    let name = event.kind;
+    match shell.spawn() {
+        Ok(mut child) => {
+            thread::spawn(move || match child.wait() {
+                Ok(status) if !status.success() => {
+                    tracing::warn!("Hook of {} failed: {}", name, status)
+                }
+                Ok(_) => {}
+                Err(error) => tracing::warn!("Hook of {} was lost: {}", name, error),
+            });
+        }
+        Err(error) => tracing::warn!("Hook of {} did not start: {}", name, error),
+    }
+}
@@ -0,0 +1,60 @@
+use crate::animations::animation::Animation;
+use crate::assets::{
+    clip::Clip,
+    locator::{AssetDir, AssetLocator},
+    manifest::PlayMode,
+    registry::AssetRegistry,
+};
+use std::process::ExitCode;
+
+// Prints what the penguins are made of: every character with its clips,
+// then the palette swaps and accessories
+pub fn run(locator: AssetLocator) -> ExitCode {
+    let registry = AssetRegistry::new(locator);
+
+    for folder in Animation::CHARACTERS {
+        let source = match registry.locator().open(folder) {
+            AssetDir::Disk(path) => path.display().to_string(),
+            AssetDir::Embedded(_) => "built-in".to_string(),
+        };
+        println!("{} ({})", folder, source);
+
+        let character = registry.character(folder);
+        let mut clips: Vec<(String, Clip)> = character
+            .clip_names()
+            .map(|name| (name.to_string(), character.clip(name)))
+            .collect();
+        if clips.is_empty() {
+            clips = registry.loose_images(folder).to_vec();
+        }
+        clips.sort_by(|a, b| a.0.cmp(&b.0));
+        for (name, clip) in clips {
+            println!(
+                "  {:<24} {:>3} frames {:>6} ms  {}",
+                name,
+                clip.frames.len(),
+                clip.duration_ms(),
+                match clip.mode {
+                    PlayMode::Loop => "loop",
+                    PlayMode::Once => "once",
+                    PlayMode::PingPong => "ping-pong",
+                }
+            );
+        }
+    }
+
+    let list = |names: Vec<&str>| match names.is_empty() {
+        true => "none".to_string(),
+        false => names.join(", "),
+    };
+    println!(
+        "Palette swaps: {}",
+        list(registry.palette_swaps().names().collect())
+    );
+    println!(
+        "Accessories: {}",
+        list(registry.accessories().names().collect())
+    );
+
+    ExitCode::SUCCESS
+}
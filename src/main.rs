@@ -1,30 +1,178 @@
 mod animations;
+mod assets;
+mod cli;
+mod config;
+mod control;
+mod hooks;
+mod list;
+mod outputs;
 mod penguin;
+mod pixelate;
+mod random;
+mod validate;
+mod watch;
 mod widgets;
 
-use iced_layershell::{reexport::Anchor, settings::LayerShellSettings};
+use assets::locator::AssetLocator;
+use clap::Parser;
+use cli::{Cli, Command, LogLevel};
+use config::{Config, ConfigSource};
+use control::socket::BindError;
+use std::process::ExitCode;
 
-use iced_layershell::application;
+use iced_layershell::settings::{LayerShellSettings, Settings, StartMode};
+
+use iced_layershell::daemon;
 use penguin::AnimatePenguin;
+use wayland_client::Connection;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match cli.log_level {
+        Some(level) => tracing_subscriber::fmt()
+            .with_max_level(match level {
+                LogLevel::Error => tracing::Level::ERROR,
+                LogLevel::Warn => tracing::Level::WARN,
+                LogLevel::Info => tracing::Level::INFO,
+                LogLevel::Debug => tracing::Level::DEBUG,
+                LogLevel::Trace => tracing::Level::TRACE,
+            })
+            .init(),
+        None => tracing_subscriber::fmt::init(),
+    }
+
+    let args = match &cli.command {
+        Some(Command::Pixelate(args)) => return pixelate::run(args),
+        Some(Command::ValidateAssets(args)) => return validate::run(args),
+        Some(Command::ListAnimations) => return list::run(AssetLocator::new(cli.assets)),
+        Some(Command::ValidateConfig) => return validate_config(&cli),
+        Some(Command::Msg(args)) => return control::client::run(args),
+        Some(Command::Run(args)) => args.clone(),
+        None => cli.run.clone(),
+    };
 
-fn main() {
-    tracing_subscriber::fmt::init();
+    let source = ConfigSource {
+        explicit: cli.config,
+        layer: args.layer,
+        walkers: args.walkers,
+        balloons: args.balloons,
+        copters: args.copters,
+        outputs: args.outputs.clone(),
+    };
+    let config = match source.load() {
+        Ok(config) => config,
+        Err(errors) => {
+            config::report(&errors);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(seed) = args.seed {
+        random::seed(seed);
+    }
+    if let Err(error) = config::watch::block_hangup() {
+        tracing::warn!("SIGHUP cannot reload the config: {}", error);
+    }
+    let listener = match control::socket_path() {
+        Some(path) => match control::socket::bind(&path) {
+            Ok(listener) => Some(listener),
+            Err(error @ BindError::Running(_)) => {
+                eprintln!(
+                    "Not starting, {}; send it commands with `wayland-penguins msg`",
+                    error
+                );
+                return ExitCode::FAILURE;
+            }
+            Err(error) => {
+                tracing::warn!("Control socket disabled: {}", error);
+                None
+            }
+        },
+        None => {
+            tracing::warn!("Control socket disabled: $XDG_RUNTIME_DIR is not set");
+            None
+        }
+    };
+    // held until the app exits, when there is no socket to do its job
+    let _lock = match listener {
+        Some(_) => None,
+        None => match control::socket::lock(&control::lock_path()) {
+            Ok(lock) => Some(lock),
+            Err(error @ BindError::Locked(_)) => {
+                eprintln!("Not starting, {}", error);
+                return ExitCode::FAILURE;
+            }
+            Err(error) => {
+                tracing::warn!("Not checking for another running instance: {}", error);
+                None
+            }
+        },
+    };
+    let locator = AssetLocator::new(cli.assets);
+    // the layer shell and the output tracking share it, so surfaces can be
+    // put on the outputs the tracking finds
+    let connection = match Connection::connect_to_env() {
+        Ok(connection) => connection,
+        Err(error) => {
+            eprintln!("Cannot connect to the Wayland compositor: {}", error);
+            if let Some(listener) = listener {
+                listener.remove();
+            }
+            return ExitCode::FAILURE;
+        }
+    };
 
-    application(
-        AnimatePenguin::new,
+    let control = listener.clone();
+    let shared = connection.clone();
+    // surfaces are opened per output once the outputs are known
+    let result = daemon(
+        move || {
+            AnimatePenguin::new(
+                locator.clone(),
+                source.clone(),
+                config.clone(),
+                control.clone(),
+                shared.clone(),
+            )
+        },
         AnimatePenguin::namespace,
         AnimatePenguin::update,
         AnimatePenguin::view,
     )
     .style(AnimatePenguin::style)
     .subscription(AnimatePenguin::subscription)
-    .layer_settings(LayerShellSettings {
-        exclusive_zone: -1,
-        anchor: Anchor::all(),
-        margin: (0, 0, 0, 0),
-        events_transparent: true,
+    .settings(Settings {
+        layer_settings: LayerShellSettings {
+            start_mode: StartMode::Background,
+            events_transparent: true,
+            ..Default::default()
+        },
+        with_connection: Some(connection),
         ..Default::default()
     })
-    .run()
-    .unwrap();
+    .run();
+
+    if let Some(listener) = listener {
+        listener.remove();
+    }
+    result.unwrap();
+
+    ExitCode::SUCCESS
+}
+
+fn validate_config(cli: &Cli) -> ExitCode {
+    let Some(path) = config::config_path(cli.config.as_deref()) else {
+        eprintln!("No config file: neither $XDG_CONFIG_HOME nor $HOME is set");
+        return ExitCode::FAILURE;
+    };
+    match Config::load(Some(&path)) {
+        Ok(_) => {
+            println!("{}: valid", path.display());
+            ExitCode::SUCCESS
+        }
+        Err(errors) => {
+            config::report(&errors);
+            ExitCode::FAILURE
+        }
+    }
 }
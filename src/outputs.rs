@@ -0,0 +1,287 @@
+use iced::futures::channel::mpsc::{self, UnboundedSender};
+use iced::Subscription;
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    thread,
+};
+use wayland_client::{
+    delegate_noop,
+    protocol::{
+        wl_output::{self, Mode, Transform, WlOutput},
+        wl_registry::{self, WlRegistry},
+    },
+    Connection, Dispatch, Proxy, QueueHandle, WEnum,
+};
+use wayland_protocols::xdg::xdg_output::zv1::client::{
+    zxdg_output_manager_v1::ZxdgOutputManagerV1,
+    zxdg_output_v1::{self, ZxdgOutputV1},
+};
+
+// A monitor as the compositor lays it out, in logical pixels
+#[derive(Debug, Clone, PartialEq)]
+pub struct Output {
+    // e.g. DP-1
+    pub name: String,
+    pub position: (i32, i32),
+    pub size: (i32, i32),
+    // layer surfaces are put on it; it belongs to the connection the app
+    // runs on
+    pub wl_output: WlOutput,
+}
+
+#[derive(Debug, Clone)]
+pub enum OutputEvent {
+    // an output was plugged in or its layout changed
+    Changed(Output),
+    // the output of that name was unplugged
+    Removed(String),
+}
+
+// The connection the app shares with the layer shell; an app has one, so
+// its subscription needs no other identity
+struct Shared(Connection);
+
+impl Hash for Shared {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+// Every output of `connection`, as it is plugged in, changes and goes away
+pub fn watch(connection: &Connection) -> Subscription<OutputEvent> {
+    Subscription::run_with(Shared(connection.clone()), |shared| {
+        let (sender, receiver) = mpsc::unbounded();
+        let connection = shared.0.clone();
+        thread::spawn(move || {
+            if let Err(error) = track(&connection, sender) {
+                tracing::error!("Outputs are not tracked: {}", error);
+            }
+        });
+        receiver
+    })
+}
+
+fn track(
+    connection: &Connection,
+    sender: UnboundedSender<OutputEvent>,
+) -> Result<(), wayland_client::DispatchError> {
+    // a queue of its own, the layer shell dispatches the default one
+    let mut queue = connection.new_event_queue();
+    connection.display().get_registry(&queue.handle(), ());
+    let mut tracker = Tracker {
+        sender,
+        manager: None,
+        outputs: HashMap::new(),
+    };
+    while !tracker.sender.is_closed() {
+        queue.blocking_dispatch(&mut tracker)?;
+    }
+    Ok(())
+}
+
+struct Tracker {
+    sender: UnboundedSender<OutputEvent>,
+    // names and logical positions come from xdg-output where there is one
+    manager: Option<ZxdgOutputManagerV1>,
+    // by the registry name of the wl_output global
+    outputs: HashMap<u32, TrackedOutput>,
+}
+
+struct TrackedOutput {
+    wl_output: WlOutput,
+    xdg_output: Option<ZxdgOutputV1>,
+    name: Option<String>,
+    position: (i32, i32),
+    size: (i32, i32),
+    // the layout wl_output gives, for compositors without xdg-output
+    wl_position: (i32, i32),
+    mode: (i32, i32),
+    scale: i32,
+    rotated: bool,
+    // what the app was last told
+    sent: Option<Output>,
+}
+
+impl TrackedOutput {
+    // Position and logical size, from xdg-output where the compositor has
+    // it and worked out from the current mode, scale and transform otherwise
+    fn layout(&self) -> ((i32, i32), (i32, i32)) {
+        if self.xdg_output.is_some() {
+            return (self.position, self.size);
+        }
+        let scale = self.scale.max(1);
+        let (width, height) = (self.mode.0 / scale, self.mode.1 / scale);
+        let size = match self.rotated {
+            true => (height, width),
+            false => (width, height),
+        };
+        (self.wl_position, size)
+    }
+}
+
+impl Tracker {
+    // Both wl_output and xdg-output end a batch of changes with `done`
+    fn done(&mut self, global: u32) {
+        let Some(tracked) = self.outputs.get_mut(&global) else {
+            return;
+        };
+        // without xdg-output, wl_output only names outputs since version 4
+        let name = match (&tracked.name, &tracked.xdg_output) {
+            (Some(name), _) => name.clone(),
+            (None, None) => format!("output-{}", global),
+            (None, Some(_)) => return,
+        };
+        let (position, size) = tracked.layout();
+        if size.0 <= 0 || size.1 <= 0 {
+            return;
+        }
+        let output = Output {
+            name,
+            position,
+            size,
+            wl_output: tracked.wl_output.clone(),
+        };
+        if tracked.sent.as_ref() != Some(&output) {
+            tracked.sent = Some(output.clone());
+            let _ = self.sender.unbounded_send(OutputEvent::Changed(output));
+        }
+    }
+}
+
+impl Dispatch<WlRegistry, ()> for Tracker {
+    fn event(
+        state: &mut Self,
+        registry: &WlRegistry,
+        event: wl_registry::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } if interface == WlOutput::interface().name => {
+                let wl_output: WlOutput = registry.bind(name, version.min(4), qh, name);
+                let xdg_output = state
+                    .manager
+                    .as_ref()
+                    .map(|manager| manager.get_xdg_output(&wl_output, qh, name));
+                state.outputs.insert(
+                    name,
+                    TrackedOutput {
+                        wl_output,
+                        xdg_output,
+                        name: None,
+                        position: (0, 0),
+                        size: (0, 0),
+                        wl_position: (0, 0),
+                        mode: (0, 0),
+                        scale: 1,
+                        rotated: false,
+                        sent: None,
+                    },
+                );
+            }
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } if interface == ZxdgOutputManagerV1::interface().name => {
+                let manager: ZxdgOutputManagerV1 = registry.bind(name, version.min(3), qh, ());
+                for (global, tracked) in &mut state.outputs {
+                    tracked.xdg_output =
+                        Some(manager.get_xdg_output(&tracked.wl_output, qh, *global));
+                }
+                state.manager = Some(manager);
+            }
+            wl_registry::Event::GlobalRemove { name } => {
+                let Some(tracked) = state.outputs.remove(&name) else {
+                    return;
+                };
+                if let Some(xdg_output) = tracked.xdg_output {
+                    xdg_output.destroy();
+                }
+                if tracked.wl_output.version() >= 3 {
+                    tracked.wl_output.release();
+                }
+                if let Some(output) = tracked.sent {
+                    let _ = state
+                        .sender
+                        .unbounded_send(OutputEvent::Removed(output.name));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<WlOutput, u32> for Tracker {
+    fn event(
+        state: &mut Self,
+        _: &WlOutput,
+        event: wl_output::Event,
+        global: &u32,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_output::Event::Done = event {
+            state.done(*global);
+            return;
+        }
+        let Some(tracked) = state.outputs.get_mut(global) else {
+            return;
+        };
+        match event {
+            // xdg-output names it too, for compositors before wl_output 4
+            wl_output::Event::Name { name } => tracked.name = Some(name),
+            wl_output::Event::Geometry {
+                x, y, transform, ..
+            } => {
+                tracked.wl_position = (x, y);
+                tracked.rotated = matches!(
+                    transform,
+                    WEnum::Value(
+                        Transform::_90
+                            | Transform::_270
+                            | Transform::Flipped90
+                            | Transform::Flipped270
+                    )
+                );
+            }
+            wl_output::Event::Mode {
+                flags: WEnum::Value(flags),
+                width,
+                height,
+                ..
+            } if flags.contains(Mode::Current) => tracked.mode = (width, height),
+            wl_output::Event::Scale { factor } => tracked.scale = factor,
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZxdgOutputV1, u32> for Tracker {
+    fn event(
+        state: &mut Self,
+        _: &ZxdgOutputV1,
+        event: zxdg_output_v1::Event,
+        global: &u32,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let Some(tracked) = state.outputs.get_mut(global) else {
+            return;
+        };
+        match event {
+            zxdg_output_v1::Event::Name { name } => tracked.name = Some(name),
+            zxdg_output_v1::Event::LogicalPosition { x, y } => tracked.position = (x, y),
+            zxdg_output_v1::Event::LogicalSize { width, height } => tracked.size = (width, height),
+            // deprecated since version 3, wl_output.done follows instead
+            zxdg_output_v1::Event::Done => state.done(*global),
+            _ => {}
+        }
+    }
+}
+
+delegate_noop!(Tracker: ZxdgOutputManagerV1);
@@ -1,37 +1,102 @@
-use crate::animations::animation::{Animation, AnimationMessage};
+use crate::animations::animation::{Animation, AnimationMessage, Edges, Entity, Kind, Side};
+use crate::assets::locator::AssetLocator;
+use crate::assets::registry::AssetRegistry;
+use crate::assets::watch::watch;
+use crate::config::{
+    self, Config, ConfigError, ConfigSource, Edge, OutputLayout, SurfaceConfig, SurfaceLayer,
+};
+use crate::control::socket::{self, Listener};
+use crate::control::{Reply, Request, Response};
+use crate::hooks;
+use crate::outputs::{self, Output, OutputEvent};
 use crate::widgets::modal::modal;
 use iced::widget::{column, container, text};
-use iced::{Color, Element, Size, Subscription, Task};
+use iced::{window, Color, Element, Rectangle, Size, Subscription, Task, Vector};
+use iced_layershell::actions::ActionCallback;
+use iced_layershell::reexport::{
+    Anchor, KeyboardInteractivity, Layer, NewLayerShellSettings, OutputOption,
+};
 use iced_layershell::to_layer_message;
-use std::sync::OnceLock;
+use std::collections::HashMap;
+use std::rc::Rc;
+use wayland_client::Connection;
 pub struct AnimatePenguin {
-    show_menu: bool,
-    screen_size: Option<Size>,
+    // the surface the menu is open on
+    menu: Option<window::Id>,
+    // one layer surface for every output the penguins are shown on
+    surfaces: HashMap<window::Id, Surface>,
+    // every output plugged in, shown on or not
+    outputs: Vec<Output>,
+    // the layer shell runs on it, outputs are tracked on it
+    connection: Connection,
+    assets: Rc<AssetRegistry>,
+    config_source: ConfigSource,
+    config: Config,
+    // a paused animation gets no ticks
+    paused: bool,
+    control: Option<Listener>,
+}
+
+// A layer surface covering one output, with the penguins walking on it
+pub struct Surface {
+    output: Output,
+    size: Option<Size>,
+    scale_factor: f32,
+    // created once the surface knows its size
     animation: Option<Animation>,
-    mainwindow: OnceLock<iced::window::Id>,
+    // the rectangles that take pointer input, none at first
+    input_region: Vec<(i32, i32, i32, i32)>,
+    // ticks left to look for the size the compositor configures the surface
+    // with after its output or its layout changed
+    relayout: u32,
 }
 
-#[to_layer_message]
+// The compositor configures a surface a little after the change that
+// caused it, so its size is looked at again for up to this many ticks
+const RELAYOUT_TICKS: u32 = 30;
+
+#[to_layer_message(multi)]
 #[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum Message {
     ScreenSizeReceived(Size),
     PlayAnimationMessage(AnimationMessage),
-    ShowMenu,
+    ShowMenu(window::Id),
     HideMenu,
-    LatestWindow(Option<iced::window::Id>),
-    SizeUpdate(iced::Size),
+    Output(OutputEvent),
+    WindowOpened(window::Id),
+    WindowClosed(window::Id),
+    SizeUpdate(window::Id, Size),
+    ScaleFactorReceived(window::Id, f32),
+    AssetsChanged(Vec<String>),
+    ConfigChanged,
+    Control(Request, Reply),
 }
 
 impl AnimatePenguin {
-    pub fn new() -> (Self, Task<Message>) {
+    pub fn new(
+        locator: AssetLocator,
+        config_source: ConfigSource,
+        config: Config,
+        control: Option<Listener>,
+        connection: Connection,
+    ) -> (Self, Task<Message>) {
+        let assets = Rc::new(AssetRegistry::new(locator));
+        Animation::preload_assets(&assets);
+
         (
             Self {
-                mainwindow: OnceLock::new(),
-                show_menu: false,
-                screen_size: None,
-                animation: None,
+                menu: None,
+                surfaces: HashMap::new(),
+                outputs: Vec::new(),
+                connection,
+                assets,
+                config_source,
+                config,
+                paused: false,
+                control,
             },
-            iced::window::latest().map(Message::LatestWindow),
+            Task::none(),
         )
     }
 
@@ -44,11 +109,28 @@ impl AnimatePenguin {
     }
 
     pub fn subscription(&self) -> iced::Subscription<Message> {
-        match &self.animation {
-            Some(animation) => animation.subscription(),
-            None => Subscription::none(),
-        }
+        // every animation ticks on the same timers, iced runs them once
+        let animations = match self.paused {
+            true => Subscription::none(),
+            false => Subscription::batch(self.animations().map(Animation::subscription)),
+        };
         // 1000ms / 16ms approx 60 fps
+
+        let outputs = outputs::watch(&self.connection).map(Message::Output);
+        let windows = Subscription::batch([
+            window::open_events().map(Message::WindowOpened),
+            window::close_events().map(Message::WindowClosed),
+        ]);
+
+        let assets = watch(self.assets.locator().search_path()).map(Message::AssetsChanged);
+
+        let config =
+            config::watch::watch(self.config_source.path()).map(|()| Message::ConfigChanged);
+
+        let control = socket::listen(self.control.clone())
+            .map(|(request, reply)| Message::Control(request, reply));
+
+        Subscription::batch([animations, outputs, windows, assets, config, control])
     }
 
     pub fn namespace() -> String {
@@ -56,47 +138,633 @@ impl AnimatePenguin {
     }
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
-        return match message {
+        let task = self.handle(message);
+        // hooks react to what the message made the penguins do
+        for surface in self.surfaces.values() {
+            let Some(animation) = &surface.animation else {
+                continue;
+            };
+            for event in animation.events() {
+                hooks::run(
+                    &self.config.hooks,
+                    &event,
+                    &surface.output.name,
+                    animation.population(),
+                );
+            }
+        }
+        Task::batch([task, self.update_input_regions()])
+    }
+
+    // Surfaces let clicks through, but for the penguins while a hook waits
+    // for clicks on them and all of the surface while the menu is open on it
+    fn update_input_regions(&mut self) -> Task<Message> {
+        let clickable = self.config.hooks.clicked.is_some();
+        let menu = self.menu;
+        let tasks: Vec<_> = self
+            .surfaces
+            .iter_mut()
+            .filter_map(|(&id, surface)| {
+                let region: Vec<(i32, i32, i32, i32)> = match (&surface.animation, surface.size) {
+                    (_, Some(size)) if menu == Some(id) => {
+                        vec![(0, 0, size.width.ceil() as i32, size.height.ceil() as i32)]
+                    }
+                    (Some(animation), _) if clickable => animation
+                        .hitboxes()
+                        .into_iter()
+                        .map(|hitbox| {
+                            (
+                                hitbox.x.floor() as i32,
+                                hitbox.y.floor() as i32,
+                                hitbox.width.ceil() as i32,
+                                hitbox.height.ceil() as i32,
+                            )
+                        })
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                if region == surface.input_region {
+                    return None;
+                }
+                surface.input_region = region.clone();
+                let callback = ActionCallback::new(move |wl_region| {
+                    // the region is shared by every surface
+                    wl_region.subtract(0, 0, i32::MAX, i32::MAX);
+                    for &(x, y, width, height) in &region {
+                        wl_region.add(x, y, width, height);
+                    }
+                });
+                Some(Task::done(Message::SetInputRegion { id, callback }))
+            })
+            .collect();
+        Task::batch(tasks)
+    }
+
+    fn handle(&mut self, message: Message) -> Task<Message> {
+        match message {
             Message::HideMenu => {
-                self.show_menu = false;
+                self.menu = None;
                 Task::none()
             }
-            Message::ShowMenu => {
-                self.show_menu = true;
+            Message::ShowMenu(id) => {
+                self.menu = Some(id);
                 Task::none()
             }
-            Message::PlayAnimationMessage(msg) => match &mut self.animation {
-                Some(animation) => animation.update(msg),
-                None => Task::none(),
-            },
-            Message::LatestWindow(None) => iced::window::latest().map(Message::LatestWindow),
-            Message::LatestWindow(Some(id)) => {
-                self.mainwindow.set(id).expect("We just set once");
-                iced::window::size(id).map(Message::SizeUpdate)
+            Message::PlayAnimationMessage(msg) => {
+                let relayout = match msg {
+                    AnimationMessage::Tick => self.follow_relayouts(),
+                    _ => Task::none(),
+                };
+                let task = Task::batch(
+                    self.animations_mut()
+                        .map(|animation| animation.update(msg.clone()))
+                        .collect::<Vec<_>>(),
+                );
+                self.hand_over();
+                Task::batch([relayout, task])
             }
-            Message::SizeUpdate(size) => {
-                self.animation = Some(Animation::new((size.width as u32, size.height as u32)));
-
-                self.screen_size = Some(size);
+            Message::Output(OutputEvent::Changed(output)) => {
+                tracing::info!(
+                    "Output {} at {:?}, {:?}",
+                    output.name,
+                    output.position,
+                    output.size
+                );
+                match self
+                    .outputs
+                    .iter_mut()
+                    .find(|known| known.name == output.name)
+                {
+                    Some(known) => *known = output.clone(),
+                    None => self.outputs.push(output.clone()),
+                }
+                match self.surface_on(&output.name) {
+                    Some(id) => {
+                        if let Some(surface) = self.surfaces.get_mut(&id) {
+                            surface.output = output;
+                            surface.relayout = RELAYOUT_TICKS;
+                        }
+                        self.update_edges();
+                        // a new scale comes with a new logical size
+                        window::scale_factor(id)
+                            .map(move |factor| Message::ScaleFactorReceived(id, factor))
+                    }
+                    None if self.config.shows_on(&output.name) => self.open_surface(output),
+                    None => Task::none(),
+                }
+            }
+            Message::Output(OutputEvent::Removed(name)) => {
+                tracing::info!("Output {} is gone", name);
+                self.outputs.retain(|output| output.name != name);
+                match self.surface_on(&name) {
+                    Some(id) => self.close_surface(id),
+                    None => Task::none(),
+                }
+            }
+            Message::WindowOpened(id) => Task::batch([
+                window::scale_factor(id)
+                    .map(move |factor| Message::ScaleFactorReceived(id, factor)),
+                window::size(id).map(move |size| Message::SizeUpdate(id, size)),
+            ]),
+            // the compositor closes the surfaces of unplugged outputs
+            Message::WindowClosed(id) => {
+                self.surfaces.remove(&id);
+                if self.menu == Some(id) {
+                    self.menu = None;
+                }
+                self.update_edges();
+                Task::none()
+            }
+            // the first size starts the penguins, later ones come from
+            // resolution changes, rotation, rescaling and a new layout
+            Message::SizeUpdate(id, size) => {
+                let Some(surface) = self.surfaces.get_mut(&id) else {
+                    return Task::none();
+                };
+                let screen_size = (size.width as u32, size.height as u32);
+                let resized = surface.size != Some(size);
+                surface.size = Some(size);
+                match &mut surface.animation {
+                    // not configured with its new size yet
+                    Some(_) if !resized => Task::none(),
+                    Some(animation) => {
+                        surface.relayout = 0;
+                        animation.set_screen_size(screen_size);
+                        window::scale_factor(id)
+                            .map(move |factor| Message::ScaleFactorReceived(id, factor))
+                    }
+                    None => {
+                        let mut animation =
+                            Animation::new(screen_size, self.assets.clone(), self.config.clone());
+                        animation.set_scale_factor(surface.scale_factor);
+                        surface.animation = Some(animation);
+                        self.update_edges();
+                        Task::none()
+                    }
+                }
+            }
+            Message::ScaleFactorReceived(id, scale_factor) => {
+                let Some(surface) = self.surfaces.get_mut(&id) else {
+                    return Task::none();
+                };
+                surface.scale_factor = scale_factor;
+                if let Some(animation) = &mut surface.animation {
+                    animation.set_scale_factor(scale_factor);
+                }
+                Task::none()
+            }
+            Message::AssetsChanged(folders) => {
+                for folder in &folders {
+                    self.assets.reload(folder);
+                    tracing::info!("Reloaded sprites of {:?}", folder);
+                }
+                for animation in self.animations_mut() {
+                    animation.reload_assets(&folders);
+                }
                 Task::none()
             }
+            // a broken config keeps the penguins going as they were
+            Message::ConfigChanged => match self.reload_config() {
+                Ok(task) => task,
+                Err(errors) => {
+                    config::report(&errors);
+                    Task::none()
+                }
+            },
+            Message::Control(request, reply) => {
+                let (response, task) = self.control(request);
+                reply.send(response);
+                task
+            }
             _ => Task::none(),
+        }
+    }
+
+    // Puts a layer surface on `output`, laid out as the config says
+    fn open_surface(&mut self, output: Output) -> Task<Message> {
+        let surface = &self.config.surface;
+        let (id, task) = Message::layershell_open(NewLayerShellSettings {
+            size: Some((surface.width, surface.height)),
+            layer: layer(surface.layer),
+            anchor: anchor(surface),
+            exclusive_zone: Some(exclusive_zone(surface)),
+            margin: Some(margin(surface)),
+            keyboard_interactivity: KeyboardInteractivity::None,
+            output_option: OutputOption::Output(output.wl_output.clone()),
+            events_transparent: true,
+            namespace: Some(Self::namespace()),
+        });
+        tracing::info!("Showing the penguins on {}", output.name);
+        self.surfaces.insert(
+            id,
+            Surface {
+                output,
+                size: None,
+                scale_factor: 1.0,
+                animation: None,
+                input_region: Vec::new(),
+                relayout: 0,
+            },
+        );
+        task
+    }
+
+    // Asks the surfaces waiting to be configured anew for their size
+    fn follow_relayouts(&mut self) -> Task<Message> {
+        let tasks: Vec<_> = self
+            .surfaces
+            .iter_mut()
+            .filter(|(_, surface)| surface.relayout > 0)
+            .map(|(&id, surface)| {
+                surface.relayout -= 1;
+                window::size(id).map(move |size| Message::SizeUpdate(id, size))
+            })
+            .collect();
+        Task::batch(tasks)
+    }
+
+    fn close_surface(&mut self, id: window::Id) -> Task<Message> {
+        self.surfaces.remove(&id);
+        if self.menu == Some(id) {
+            self.menu = None;
+        }
+        self.update_edges();
+        window::close(id)
+    }
+
+    // The surface with penguins on the output next to that of `id` on
+    // `side`, the two surfaces sharing part of their height
+    fn neighbour(&self, id: window::Id, side: Side) -> Option<window::Id> {
+        let output = &self.surfaces.get(&id)?.output;
+        let placement = self.placement(id)?;
+        let (x, width) = (output.position.0, output.size.0);
+        self.surfaces
+            .iter()
+            .filter(|(other, surface)| **other != id && surface.animation.is_some())
+            .find(|(other, surface)| {
+                let (other_x, other_width) = (surface.output.position.0, surface.output.size.0);
+                let touching = match side {
+                    Side::Left => other_x + other_width == x,
+                    Side::Right => other_x == x + width,
+                };
+                let Some(other) = self.placement(**other) else {
+                    return false;
+                };
+                touching
+                    && other.y < placement.y + placement.height
+                    && placement.y < other.y + other.height
+            })
+            .map(|(id, _)| *id)
+    }
+
+    // Where the surface `id` sits in the layout of the outputs. The
+    // compositor places it inside its output by its anchors and margins;
+    // the room panels keep free is not told, so a surface anchored on both
+    // ends of an axis is taken to give up as much on either end.
+    fn placement(&self, id: window::Id) -> Option<Rectangle> {
+        let surface = self.surfaces.get(&id)?;
+        let size = surface.size?;
+        let config = &self.config.surface;
+        let [top, right, bottom, left] = config.margin;
+        let output = &surface.output;
+        Some(Rectangle {
+            x: place(
+                (output.position.0, output.size.0),
+                size.width,
+                (config.anchored(Edge::Left), left),
+                (config.anchored(Edge::Right), right),
+            ),
+            y: place(
+                (output.position.1, output.size.1),
+                size.height,
+                (config.anchored(Edge::Top), top),
+                (config.anchored(Edge::Bottom), bottom),
+            ),
+            width: size.width,
+            height: size.height,
+        })
+    }
+
+    // Opens the edges of every screen that lead onto another one
+    fn update_edges(&mut self) {
+        let edges: Vec<(window::Id, Edges)> = self
+            .surfaces
+            .keys()
+            .map(|id| match self.config.output_layout {
+                OutputLayout::Wrap => (
+                    *id,
+                    Edges {
+                        left: self.neighbour(*id, Side::Left).is_some(),
+                        right: self.neighbour(*id, Side::Right).is_some(),
+                    },
+                ),
+                OutputLayout::Independent => (*id, Edges::default()),
+            })
+            .collect();
+        for (id, edges) in edges {
+            if let Some(animation) = self
+                .surfaces
+                .get_mut(&id)
+                .and_then(|surface| surface.animation.as_mut())
+            {
+                animation.set_edges(edges);
+            }
+        }
+    }
+
+    // Moves the penguins that went over an edge to the output on that side;
+    // one that finds no output there, e.g. as it was unplugged, comes back
+    fn hand_over(&mut self) {
+        let ids: Vec<window::Id> = self.surfaces.keys().copied().collect();
+        for id in ids {
+            let Some(animation) = self
+                .surfaces
+                .get_mut(&id)
+                .and_then(|surface| surface.animation.as_mut())
+            else {
+                continue;
+            };
+            for departure in animation.departures() {
+                let target = self.neighbour(id, departure.side).unwrap_or(id);
+                // from positions on the surface left to positions on the
+                // surface entered
+                let offset = match (self.placement(id), self.placement(target)) {
+                    (Some(from), Some(to)) => from.position() - to.position(),
+                    _ => Vector::new(0.0, 0.0),
+                };
+                if let Some(animation) = self
+                    .surfaces
+                    .get_mut(&target)
+                    .and_then(|surface| surface.animation.as_mut())
+                {
+                    animation.arrive(departure, offset);
+                }
+            }
+        }
+    }
+
+    fn surface_on(&self, output: &str) -> Option<window::Id> {
+        self.surfaces
+            .iter()
+            .find(|(_, surface)| surface.output.name == output)
+            .map(|(id, _)| *id)
+    }
+
+    // Opens and closes surfaces until the penguins are on the outputs the
+    // config names
+    fn show_on_outputs(&mut self) -> Task<Message> {
+        let closed: Vec<window::Id> = self
+            .surfaces
+            .iter()
+            .filter(|(_, surface)| !self.config.shows_on(&surface.output.name))
+            .map(|(id, _)| *id)
+            .collect();
+        let opened: Vec<Output> = self
+            .outputs
+            .iter()
+            .filter(|output| self.config.shows_on(&output.name))
+            .filter(|output| self.surface_on(&output.name).is_none())
+            .cloned()
+            .collect();
+
+        let closing = closed.into_iter().map(|id| self.close_surface(id));
+        let closing: Vec<_> = closing.collect();
+        let opening: Vec<_> = opened
+            .into_iter()
+            .map(|output| self.open_surface(output))
+            .collect();
+        Task::batch(closing.into_iter().chain(opening))
+    }
+
+    fn animations(&self) -> impl Iterator<Item = &Animation> {
+        self.surfaces
+            .values()
+            .filter_map(|surface| surface.animation.as_ref())
+    }
+
+    fn animations_mut(&mut self) -> impl Iterator<Item = &mut Animation> {
+        self.surfaces
+            .values_mut()
+            .filter_map(|surface| surface.animation.as_mut())
+    }
+
+    // Keeps the current config when the file is broken
+    fn reload_config(&mut self) -> Result<Task<Message>, Vec<ConfigError>> {
+        let config = self.config_source.load()?;
+        if config == self.config {
+            return Ok(Task::none());
+        }
+        tracing::info!("Reloaded the config");
+        Ok(self.set_config(config))
+    }
+
+    fn set_config(&mut self, config: Config) -> Task<Message> {
+        for animation in self.animations_mut() {
+            animation.set_config(config.clone());
+        }
+        let relayout = match config.surface == self.config.surface {
+            true => Task::none(),
+            false => self.lay_out_surfaces(&config.surface),
         };
+        self.config = config;
+        let task = self.show_on_outputs();
+        self.update_edges();
+        Task::batch([relayout, task])
+    }
+
+    // Moves the open surfaces to their new layer and place; the penguins
+    // follow once the surfaces are resized
+    fn lay_out_surfaces(&mut self, surface: &SurfaceConfig) -> Task<Message> {
+        for open in self.surfaces.values_mut() {
+            open.relayout = RELAYOUT_TICKS;
+        }
+        Task::batch(self.surfaces.keys().flat_map(|&id| {
+            [
+                Message::LayerChange {
+                    id,
+                    layer: layer(surface.layer),
+                },
+                Message::AnchorSizeChange {
+                    id,
+                    anchor: anchor(surface),
+                    size: (surface.width, surface.height),
+                },
+                Message::MarginChange {
+                    id,
+                    margin: margin(surface),
+                },
+                Message::ExclusiveZoneChange {
+                    id,
+                    zone_size: exclusive_zone(surface),
+                },
+            ]
+            .map(Task::done)
+        }))
+    }
+
+    // Surfaces with penguins, from the leftmost output on
+    fn shown(&mut self) -> Vec<(&Output, &mut Animation)> {
+        let mut shown: Vec<_> = self
+            .surfaces
+            .values_mut()
+            .filter_map(|surface| Some((&surface.output, surface.animation.as_mut()?)))
+            .collect();
+        shown.sort_by_key(|(output, _)| output.position);
+        shown
+    }
+
+    fn control(&mut self, request: Request) -> (Response, Task<Message>) {
+        match request {
+            // on the leftmost output
+            Request::ShowMenu => {
+                self.menu = self
+                    .surfaces
+                    .iter()
+                    .min_by_key(|(_, surface)| surface.output.position)
+                    .map(|(id, _)| *id);
+                match self.menu {
+                    Some(_) => (Response::ok(), Task::none()),
+                    None => (
+                        Response::error("the overlay is not shown yet"),
+                        Task::none(),
+                    ),
+                }
+            }
+            Request::HideMenu => {
+                self.menu = None;
+                (Response::ok(), Task::none())
+            }
+            Request::Pause | Request::Resume => {
+                self.paused = matches!(request, Request::Pause);
+                (Response::ok(), Task::none())
+            }
+            Request::ReloadConfig => match self.reload_config() {
+                Ok(task) => (Response::ok(), task),
+                Err(errors) => (
+                    Response::error(
+                        errors
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    ),
+                    Task::none(),
+                ),
+            },
+            // lasts until the config is reloaded
+            Request::Speed { kind, multiplier } => {
+                if !(multiplier.is_finite() && multiplier > 0.0) {
+                    return (
+                        Response::error(format!(
+                            "multiplier must be a number above 0, not {}",
+                            multiplier
+                        )),
+                        Task::none(),
+                    );
+                }
+                let mut config = self.config.clone();
+                let speed = &mut config.speed;
+                match kind {
+                    Some(Kind::Walker) => speed.walk = multiplier,
+                    Some(Kind::Balloon) => speed.balloon = multiplier,
+                    Some(Kind::Copter) => speed.copter = multiplier,
+                    None => {
+                        speed.walk = multiplier;
+                        speed.balloon = multiplier;
+                        speed.copter = multiplier;
+                    }
+                }
+                (Response::ok(), self.set_config(config))
+            }
+            Request::Spawn { kind, count } => (
+                self.with_animations(|mut shown| {
+                    // one at a time on the emptiest output
+                    let mut spawned = 0;
+                    for _ in 0..count {
+                        let Some((_, animation)) = shown
+                            .iter_mut()
+                            .min_by_key(|(_, animation)| animation.population())
+                        else {
+                            break;
+                        };
+                        match animation.spawn(kind, 1) {
+                            0 => break,
+                            _ => spawned += 1,
+                        }
+                    }
+                    Response {
+                        count: Some(spawned),
+                        ..Response::ok()
+                    }
+                }),
+                Task::none(),
+            ),
+            Request::Despawn { kind, count } => (
+                self.with_animations(|shown| {
+                    let mut sent_off = 0;
+                    for (_, animation) in shown {
+                        let left = count.map(|count| count - sent_off);
+                        if left == Some(0) {
+                            break;
+                        }
+                        sent_off += animation.despawn(kind, left);
+                    }
+                    Response {
+                        count: Some(sent_off),
+                        ..Response::ok()
+                    }
+                }),
+                Task::none(),
+            ),
+            Request::List => {
+                let paused = self.paused;
+                (
+                    self.with_animations(|shown| Response {
+                        paused: Some(paused),
+                        entities: Some(
+                            shown
+                                .iter()
+                                .flat_map(|(output, animation)| {
+                                    animation.entities().into_iter().map(|entity| Entity {
+                                        output: Some(output.name.clone()),
+                                        ..entity
+                                    })
+                                })
+                                .collect(),
+                        ),
+                        ..Response::ok()
+                    }),
+                    Task::none(),
+                )
+            }
+        }
+    }
+
+    // Penguins only exist once a surface knows its size
+    fn with_animations(
+        &mut self,
+        action: impl FnOnce(Vec<(&Output, &mut Animation)>) -> Response,
+    ) -> Response {
+        let shown = self.shown();
+        match shown.is_empty() {
+            true => Response::error("the overlay is not shown yet"),
+            false => action(shown),
+        }
     }
 
-    pub fn view(&'_ self) -> Element<'_, Message> {
-        let Some(screen_size) = self.screen_size else {
+    pub fn view(&'_ self, id: window::Id) -> Element<'_, Message> {
+        let Some(surface) = self.surfaces.get(&id) else {
             return text("").into();
         };
-        let Some(animation) = &self.animation else {
+        let (Some(size), Some(animation)) = (surface.size, &surface.animation) else {
             return text("").into();
         };
-        let x = screen_size.width / 2.5;
-        let y = screen_size.height / 2.5;
+        let x = size.width / 2.5;
+        let y = size.height / 2.5;
 
         let content = animation.view();
 
-        if self.show_menu {
+        if self.menu == Some(id) {
             // TODO
             let menu = container(column![text("Penguin Walking Animation").size(24),].spacing(20))
                 .style(|_theme| container::Style {
@@ -106,13 +774,67 @@ impl AnimatePenguin {
                     text_color: Some(Color::BLACK),
                     ..container::Style::default()
                 })
-                .width(x as f32)
-                .height(y as f32)
+                .width(x)
+                .height(y)
                 .padding(20);
 
-            modal(content, menu, Message::HideMenu).into()
+            modal(content, menu, Message::HideMenu)
         } else {
-            content.into()
+            content
         }
     }
 }
+
+fn layer(layer: SurfaceLayer) -> Layer {
+    match layer {
+        SurfaceLayer::Background => Layer::Background,
+        SurfaceLayer::Bottom => Layer::Bottom,
+        SurfaceLayer::Top => Layer::Top,
+        SurfaceLayer::Overlay => Layer::Overlay,
+    }
+}
+
+fn anchor(surface: &SurfaceConfig) -> Anchor {
+    surface
+        .anchor
+        .iter()
+        .fold(Anchor::empty(), |anchor, edge| match edge {
+            Edge::Top => anchor | Anchor::Top,
+            Edge::Bottom => anchor | Anchor::Bottom,
+            Edge::Left => anchor | Anchor::Left,
+            Edge::Right => anchor | Anchor::Right,
+        })
+}
+
+fn margin(surface: &SurfaceConfig) -> (i32, i32, i32, i32) {
+    let [top, right, bottom, left] = surface.margin;
+    (top, right, bottom, left)
+}
+
+// Start of a surface `size` long on an axis of its output, given by where
+// the output starts and how long it is, with the anchor and margin at
+// either end
+fn place(
+    (start, length): (i32, i32),
+    size: f32,
+    (anchored_start, margin_start): (bool, i32),
+    (anchored_end, margin_end): (bool, i32),
+) -> f32 {
+    let (start, length) = (start as f32, length as f32);
+    let (margin_start, margin_end) = (margin_start as f32, margin_end as f32);
+    match (anchored_start, anchored_end) {
+        (true, false) => start + margin_start,
+        (false, true) => start + length - margin_end - size,
+        (true, true) => start + margin_start + (length - margin_start - margin_end - size) / 2.0,
+        // centred, margins only count on anchored edges
+        (false, false) => start + (length - size) / 2.0,
+    }
+}
+
+// 0 keeps out of the zones panels reserve, -1 reaches under them
+fn exclusive_zone(surface: &SurfaceConfig) -> i32 {
+    match surface.respect_panels {
+        true => 0,
+        false => -1,
+    }
+}
@@ -0,0 +1,344 @@
+use crate::assets::palette::Palette;
+use crate::cli::PixelateArgs;
+use ::image::{ImageError, ImageFormat, Rgba, RgbaImage};
+use clap::ValueEnum;
+use std::{
+    fs::{create_dir_all, read_dir},
+    io,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Dither {
+    // Every block takes its nearest palette colour
+    None,
+    // 4x4 Bayer threshold pattern
+    Ordered,
+    // Error diffusion to the neighbouring blocks
+    FloydSteinberg,
+}
+
+// 4x4 Bayer matrix, thresholds 0..16
+const BAYER: [[f32; 4]; 4] = [
+    [0.0, 8.0, 2.0, 10.0],
+    [12.0, 4.0, 14.0, 6.0],
+    [3.0, 11.0, 1.0, 9.0],
+    [15.0, 7.0, 13.0, 5.0],
+];
+
+// How far (per channel) the ordered pattern pushes a colour
+const ORDERED_SPREAD: f32 = 32.0;
+
+// Converts every frame the arguments name and writes `<prefix><name>.png`
+// next to it (or into `--output`), the names the sprite manifests refer to.
+// The frames of a folder go into a folder of their own, since a clip loads
+// every image of its `directory`. Already converted frames are skipped, so
+// the command can be rerun on the same folder.
+pub fn run(args: &PixelateArgs) -> ExitCode {
+    let palette = match &args.palette {
+        Some(path) => match Palette::load(path) {
+            Ok(palette) => palette,
+            Err(error) => {
+                eprintln!("Pixelate error: {}: {}", path.display(), error);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Palette::default(),
+    };
+
+    let jobs = match jobs(args) {
+        Ok(jobs) => jobs,
+        Err(error) => {
+            eprintln!("Pixelate error: {}: {}", args.input.display(), error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut failed = false;
+    for (input, output) in jobs {
+        match convert(&input, &output, args.block_size, &palette, args.dither) {
+            Ok(()) => println!("{} -> {}", input.display(), output.display()),
+            Err(error) => {
+                eprintln!("Pixelate error: {}: {}", input.display(), error);
+                failed = true;
+            }
+        }
+    }
+
+    match failed {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
+    }
+}
+
+// (input, output) path of every frame to convert
+fn jobs(args: &PixelateArgs) -> io::Result<Vec<(PathBuf, PathBuf)>> {
+    let output_name = |input: &Path| {
+        let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+        format!("{}{}.png", args.prefix, stem)
+    };
+
+    if args.input.is_file() {
+        let output_dir = match &args.output {
+            Some(dir) => dir.clone(),
+            None => args.input.parent().unwrap_or(Path::new("")).to_path_buf(),
+        };
+        let output = output_dir.join(output_name(&args.input));
+        return Ok(vec![(args.input.clone(), output)]);
+    }
+
+    let output_root = match &args.output {
+        Some(dir) => dir.clone(),
+        None => output_dir(&args.input, &args.prefix)?,
+    };
+    let mut jobs = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(relative) = pending.pop() {
+        let mut entries = read_dir(args.input.join(&relative))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+
+        for path in entries {
+            let name = path.file_name().unwrap_or_default();
+            if path.is_dir() {
+                if args.recursive {
+                    pending.push(relative.join(name));
+                }
+                continue;
+            }
+
+            let converted =
+                !args.prefix.is_empty() && name.to_string_lossy().starts_with(args.prefix.as_str());
+            if converted || ImageFormat::from_path(&path).is_err() {
+                continue;
+            }
+
+            let output = output_root.join(&relative).join(output_name(&path));
+            jobs.push((path, output));
+        }
+    }
+
+    Ok(jobs)
+}
+
+// `<prefix><name>` next to the folder `input`, e.g. `pixelated-Right Animation`
+fn output_dir(input: &Path, prefix: &str) -> io::Result<PathBuf> {
+    let input = input.canonicalize()?;
+    match (input.parent(), input.file_name()) {
+        (Some(parent), Some(name)) if !prefix.is_empty() => {
+            Ok(parent.join(format!("{}{}", prefix, name.to_string_lossy())))
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the converted frames need a folder of their own, pass --output",
+        )),
+    }
+}
+
+fn convert(
+    input: &Path,
+    output: &Path,
+    block_size: u32,
+    palette: &Palette,
+    dither: Dither,
+) -> Result<(), ImageError> {
+    let image = ::image::open(input)?.to_rgba8();
+    let pixelated = pixelate(&image, block_size, palette, dither);
+
+    if let Some(dir) = output.parent() {
+        create_dir_all(dir)?;
+    }
+    pixelated.save_with_format(output, ImageFormat::Png)
+}
+
+// Averages every `block_size` square, snaps it to the palette and scales the
+// result back up, so the frame keeps its size. Blocks end up either fully
+// transparent or opaque.
+pub fn pixelate(
+    image: &RgbaImage,
+    block_size: u32,
+    palette: &Palette,
+    dither: Dither,
+) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let block_size = block_size.max(1);
+    let columns = width.div_ceil(block_size);
+    let rows = height.div_ceil(block_size);
+
+    // colours are weighted by alpha so transparent pixels do not darken edges
+    let mut blocks = Vec::with_capacity((columns * rows) as usize);
+    for row in 0..rows {
+        for column in 0..columns {
+            let mut sum = [0.0f32; 4];
+            let mut pixels = 0.0;
+            for y in row * block_size..((row + 1) * block_size).min(height) {
+                for x in column * block_size..((column + 1) * block_size).min(width) {
+                    let Rgba([red, green, blue, alpha]) = *image.get_pixel(x, y);
+                    let alpha = alpha as f32;
+                    sum[0] += red as f32 * alpha;
+                    sum[1] += green as f32 * alpha;
+                    sum[2] += blue as f32 * alpha;
+                    sum[3] += alpha;
+                    pixels += 1.0;
+                }
+            }
+
+            blocks.push(match sum[3] / pixels >= 128.0 {
+                true => Some([sum[0] / sum[3], sum[1] / sum[3], sum[2] / sum[3]]),
+                false => None,
+            });
+        }
+    }
+
+    let colors = quantize(&mut blocks, columns as usize, palette, dither);
+
+    RgbaImage::from_fn(width, height, |x, y| {
+        let block = (y / block_size * columns + x / block_size) as usize;
+        match colors[block] {
+            Some([red, green, blue]) => Rgba([red, green, blue, 255]),
+            None => Rgba([0, 0, 0, 0]),
+        }
+    })
+}
+
+// Snaps every opaque block to the palette, row by row
+fn quantize(
+    blocks: &mut [Option<[f32; 3]>],
+    columns: usize,
+    palette: &Palette,
+    dither: Dither,
+) -> Vec<Option<[u8; 3]>> {
+    let mut colors = Vec::with_capacity(blocks.len());
+
+    for idx in 0..blocks.len() {
+        let Some(mut color) = blocks[idx] else {
+            colors.push(None);
+            continue;
+        };
+        let (column, row) = (idx % columns, idx / columns);
+
+        if dither == Dither::Ordered {
+            let threshold = BAYER[row % 4][column % 4] / 16.0 - 0.5;
+            color = color.map(|channel| channel + threshold * ORDERED_SPREAD);
+        }
+
+        let snapped = palette.nearest(color);
+        colors.push(Some(snapped));
+
+        if dither == Dither::FloydSteinberg {
+            let error: [f32; 3] = std::array::from_fn(|c| color[c] - snapped[c] as f32);
+            let last_column = column + 1 == columns;
+            let mut spread = |target: usize, weight: f32| {
+                if let Some(Some(neighbour)) = blocks.get_mut(target) {
+                    for c in 0..3 {
+                        neighbour[c] += error[c] * weight;
+                    }
+                }
+            };
+            if !last_column {
+                spread(idx + 1, 7.0 / 16.0);
+            }
+            if column > 0 {
+                spread(idx + columns - 1, 3.0 / 16.0);
+            }
+            spread(idx + columns, 5.0 / 16.0);
+            if !last_column {
+                spread(idx + columns + 1, 1.0 / 16.0);
+            }
+        }
+    }
+
+    colors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLEAR: Rgba<u8> = Rgba([0, 0, 0, 0]);
+    const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+    const LIGHT: [u8; 3] = [184, 170, 176];
+    const DARK: [u8; 3] = [121, 112, 126];
+
+    fn grey() -> Palette {
+        Palette::new(vec![[0, 0, 0], [128, 128, 128], [255, 255, 255]]).unwrap()
+    }
+
+    #[test]
+    fn blocks_take_their_average_colour() {
+        // black and white columns, two pixels wide
+        let image = RgbaImage::from_fn(4, 2, |x, _| match x % 2 {
+            0 => Rgba([0, 0, 0, 255]),
+            _ => WHITE,
+        });
+        let pixelated = pixelate(&image, 2, &grey(), Dither::None);
+        assert_eq!(pixelated.dimensions(), (4, 2));
+        assert!(pixelated
+            .pixels()
+            .all(|pixel| *pixel == Rgba([128, 128, 128, 255])));
+    }
+
+    #[test]
+    fn mostly_transparent_blocks_are_cut() {
+        // one opaque pixel in the left block, three in the right one
+        let image = RgbaImage::from_fn(4, 2, |x, y| match (x, y) {
+            (0, 0) | (2, 0) | (3, 0) | (3, 1) => WHITE,
+            _ => CLEAR,
+        });
+        let pixelated = pixelate(&image, 2, &grey(), Dither::None);
+        assert_eq!(pixelated.get_pixel(0, 0), &CLEAR);
+        assert_eq!(pixelated.get_pixel(1, 1), &CLEAR);
+        // transparent pixels do not darken the rest
+        assert_eq!(pixelated.get_pixel(2, 1), &WHITE);
+    }
+
+    #[test]
+    fn partial_blocks_at_the_edges_are_kept() {
+        let image = RgbaImage::from_pixel(3, 3, WHITE);
+        let pixelated = pixelate(&image, 2, &grey(), Dither::None);
+        assert_eq!(pixelated.dimensions(), (3, 3));
+        assert_eq!(pixelated.get_pixel(2, 2), &WHITE);
+    }
+
+    // Two rows of four blocks of a grey between two palette colours
+    fn dithered(dither: Dither) -> Vec<Option<[u8; 3]>> {
+        let mut blocks = vec![Some([160.0, 150.0, 150.0]); 8];
+        quantize(&mut blocks, 4, &Palette::default(), dither)
+    }
+
+    #[test]
+    fn undithered_blocks_take_the_nearest_colour() {
+        assert_eq!(dithered(Dither::None), [Some(LIGHT); 8]);
+    }
+
+    #[test]
+    fn ordered_dithering_follows_the_bayer_pattern() {
+        let expected = [
+            DARK, LIGHT, DARK, LIGHT, //
+            LIGHT, DARK, LIGHT, LIGHT,
+        ];
+        assert_eq!(dithered(Dither::Ordered), expected.map(Some));
+    }
+
+    #[test]
+    fn floyd_steinberg_spreads_the_error() {
+        let expected = [
+            LIGHT, DARK, LIGHT, DARK, //
+            LIGHT, DARK, LIGHT, LIGHT,
+        ];
+        assert_eq!(dithered(Dither::FloydSteinberg), expected.map(Some));
+    }
+
+    #[test]
+    fn transparent_blocks_stay_transparent() {
+        let mut blocks = vec![
+            Some([160.0, 150.0, 150.0]),
+            None,
+            Some([160.0, 150.0, 150.0]),
+        ];
+        let colors = quantize(&mut blocks, 3, &Palette::default(), Dither::FloydSteinberg);
+        assert_eq!(colors[1], None);
+    }
+}
@@ -0,0 +1,17 @@
+use rand::{rngs::StdRng, SeedableRng};
+use std::sync::{LazyLock, Mutex, MutexGuard};
+
+// Every random choice (spawn positions, turn points, palette swaps,
+// accessories...) draws from this generator, so a fixed `--seed` gives the
+// same penguins on every run
+static RNG: LazyLock<Mutex<StdRng>> = LazyLock::new(|| Mutex::new(StdRng::from_os_rng()));
+
+pub fn seed(seed: u64) {
+    *rng() = StdRng::seed_from_u64(seed);
+}
+
+// Locks the generator until the guard is dropped; draw and drop it in one
+// statement, e.g. `random::rng().random_range(0..10)`
+pub fn rng() -> MutexGuard<'static, StdRng> {
+    RNG.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
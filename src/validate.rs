@@ -0,0 +1,414 @@
+use crate::animations::animation::Animation;
+use crate::assets::{
+    accessory::ACCESSORIES_FOLDER,
+    clip::Clip,
+    locator::{AssetDir, AssetLocator},
+    manifest::{CharacterManifest, MANIFEST_FILE},
+    recolor::PALETTES_FOLDER,
+    registry::{AssetRegistry, Character},
+    sprite::Sprite,
+};
+use crate::cli::ValidateAssetsArgs;
+use iced::widget::image;
+use serde::Serialize;
+use std::{collections::BTreeMap, fs::read_dir, path::Path, process::ExitCode};
+
+// Printed to stdout as JSON
+#[derive(Serialize)]
+struct Report {
+    pack: String,
+    valid: bool,
+    errors: usize,
+    warnings: usize,
+    characters: Vec<CharacterReport>,
+    palette_swaps: Vec<String>,
+    accessories: Vec<String>,
+    issues: Vec<Issue>,
+}
+
+#[derive(Serialize)]
+struct CharacterReport {
+    folder: String,
+    // "pack", or "built-in" when the pack does not provide the folder
+    source: &'static str,
+    clips: Vec<ClipReport>,
+}
+
+#[derive(Serialize)]
+struct ClipReport {
+    name: String,
+    frames: usize,
+    width: Option<u32>,
+    height: Option<u32>,
+    duration_ms: u32,
+}
+
+#[derive(Serialize)]
+struct Issue {
+    severity: Severity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    folder: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    clip: Option<String>,
+    message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Error,
+    Warning,
+}
+
+impl Issue {
+    fn new(severity: Severity, folder: &str, clip: Option<&str>, message: String) -> Self {
+        Self {
+            severity,
+            folder: Some(folder.to_string()),
+            clip: clip.map(str::to_string),
+            message,
+        }
+    }
+}
+
+// Loads the pack in `args.dir` the way the animations do at startup, then
+// decodes every frame of the folders the pack provides and checks their
+// sizes, frame counts and file numbering. Exits with failure when any check
+// reports an error; warnings alone pass.
+pub fn run(args: &ValidateAssetsArgs) -> ExitCode {
+    let mut issues = Vec::new();
+    let mut characters = Vec::new();
+    let mut palette_swaps = Vec::new();
+    let mut accessories = Vec::new();
+
+    match pack_folders(&args.dir) {
+        Ok(folders) => {
+            let registry = AssetRegistry::collecting(AssetLocator::pack(args.dir.clone()));
+            Animation::preload_assets(&registry);
+
+            for folder in folders {
+                characters.push(check_character(&registry, &folder, &mut issues));
+            }
+            palette_swaps = registry
+                .palette_swaps()
+                .names()
+                .map(str::to_string)
+                .collect();
+            accessories = registry.accessories().names().map(str::to_string).collect();
+
+            issues.splice(
+                0..0,
+                registry.take_errors().into_iter().map(|error| Issue {
+                    severity: Severity::Error,
+                    folder: None,
+                    clip: None,
+                    message: error.to_string(),
+                }),
+            );
+        }
+        Err(error) => issues.push(Issue {
+            severity: Severity::Error,
+            folder: None,
+            clip: None,
+            message: format!("{}: {}", args.dir.display(), error),
+        }),
+    }
+
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .count();
+    let report = Report {
+        pack: args.dir.display().to_string(),
+        valid: errors == 0,
+        errors,
+        warnings: issues.len() - errors,
+        characters,
+        palette_swaps,
+        accessories,
+        issues,
+    };
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&report).expect("the report is plain data")
+    );
+
+    match report.valid {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
+
+// The characters the animations use, then any other folder in the pack
+// except the palette swaps and accessories
+fn pack_folders(dir: &Path) -> std::io::Result<Vec<String>> {
+    let mut extra = read_dir(dir)?
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|folder| !Animation::CHARACTERS.contains(&folder.as_str()))
+        .filter(|folder| folder != PALETTES_FOLDER && folder != ACCESSORIES_FOLDER)
+        .collect::<Vec<_>>();
+    extra.sort();
+
+    Ok(Animation::CHARACTERS
+        .iter()
+        .map(|folder| folder.to_string())
+        .chain(extra)
+        .collect())
+}
+
+fn check_character(
+    registry: &AssetRegistry,
+    folder: &str,
+    issues: &mut Vec<Issue>,
+) -> CharacterReport {
+    let dir = registry.locator().open(folder);
+    let in_pack = matches!(dir, AssetDir::Disk(_));
+    let character = registry.character(folder);
+
+    let mut clips: Vec<(String, Clip)> = character
+        .clip_names()
+        .map(|name| (name.to_string(), character.clip(name)))
+        .collect();
+    if clips.is_empty() {
+        clips = registry.loose_images(folder).to_vec();
+    }
+    clips.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let clips = clips
+        .iter()
+        .map(|(name, clip)| {
+            // the built-in sprites ship with the binary, only the pack is checked
+            let size = match in_pack {
+                true => check_frames(folder, name, clip, issues),
+                false => None,
+            };
+            ClipReport {
+                name: name.clone(),
+                frames: clip.frames.len(),
+                width: size.map(|(width, _)| width),
+                height: size.map(|(_, height)| height),
+                duration_ms: clip.duration_ms(),
+            }
+        })
+        .collect();
+
+    if in_pack {
+        check_manifest(&dir, folder, &character, issues);
+    }
+
+    CharacterReport {
+        folder: folder.to_string(),
+        source: match in_pack {
+            true => "pack",
+            false => "built-in",
+        },
+        clips,
+    }
+}
+
+// Decodes every frame and returns their common size
+fn check_frames(
+    folder: &str,
+    name: &str,
+    clip: &Clip,
+    issues: &mut Vec<Issue>,
+) -> Option<(u32, u32)> {
+    let mut size = None;
+
+    for (idx, frame) in clip.frames.iter().enumerate() {
+        // placeholder frames stand in for problems reported while loading
+        let dimensions = match frame {
+            Sprite::Raster(image::Handle::Bytes(_, bytes)) => {
+                ::image::load_from_memory(bytes).map(|decoded| (decoded.width(), decoded.height()))
+            }
+            Sprite::Vector(vector) => {
+                let (width, height) = vector.size();
+                Ok((width.round() as u32, height.round() as u32))
+            }
+            _ => continue,
+        };
+        let dimensions = match dimensions {
+            Ok(dimensions) => dimensions,
+            Err(error) => {
+                issues.push(Issue::new(
+                    Severity::Error,
+                    folder,
+                    Some(name),
+                    format!("frame {}: failed to decode image: {}", idx, error),
+                ));
+                continue;
+            }
+        };
+
+        match size {
+            None => size = Some(dimensions),
+            Some(first) if first != dimensions => issues.push(Issue::new(
+                Severity::Error,
+                folder,
+                Some(name),
+                format!(
+                    "frame {} is {}x{} but the first frame is {}x{}",
+                    idx, dimensions.0, dimensions.1, first.0, first.1
+                ),
+            )),
+            Some(_) => {}
+        }
+    }
+
+    if let Some((width, height)) = size {
+        let (sprite_width, sprite_height) = clip.sprite_size;
+        if (width as f32 / height as f32 - sprite_width / sprite_height).abs() > 0.01 {
+            issues.push(Issue::new(
+                Severity::Warning,
+                folder,
+                Some(name),
+                format!(
+                    "{}x{} frames are stretched to the {}x{} sprite size",
+                    width, height, sprite_width, sprite_height
+                ),
+            ));
+        }
+    }
+
+    size
+}
+
+// Checks what only the manifest knows: declared frame counts, per-frame
+// durations and the numbering of frame folders
+fn check_manifest(dir: &AssetDir, folder: &str, character: &Character, issues: &mut Vec<Issue>) {
+    // unreadable or invalid manifests were reported while loading
+    let Ok(bytes) = dir.read(MANIFEST_FILE) else {
+        return;
+    };
+    let Ok(manifest) = CharacterManifest::parse(&String::from_utf8_lossy(&bytes)) else {
+        return;
+    };
+
+    let clips: BTreeMap<_, _> = manifest.clips.iter().collect();
+    for (name, clip) in clips {
+        let mut issue = |severity, message| {
+            issues.push(Issue::new(severity, folder, Some(name), message));
+        };
+        let frames = character.clip(name).frames.len();
+
+        if let Some(frame_count) = clip.frame_count {
+            if frames < frame_count {
+                issue(
+                    Severity::Error,
+                    format!("declares {} frames but only {} load", frame_count, frames),
+                );
+            }
+        }
+
+        if let Some(durations) = &clip.durations {
+            if durations.len() != frames {
+                issue(
+                    Severity::Warning,
+                    format!("{} durations for {} frames", durations.len(), frames),
+                );
+            }
+        }
+
+        if let Some(directory) = &clip.directory {
+            if let Ok(files) = dir.list(directory) {
+                for message in numbering_problems(&files) {
+                    issue(Severity::Error, message);
+                }
+            }
+        }
+    }
+}
+
+// Frames play in file name order; when every name ends in a number, the
+// numbers should count up without gaps or repeats in that same order
+fn numbering_problems(files: &[String]) -> Vec<String> {
+    let numbered: Option<Vec<(u32, &String)>> = files
+        .iter()
+        .map(|file| frame_number(file).map(|number| (number, file)))
+        .collect();
+    let Some(numbered) = numbered else {
+        return Vec::new();
+    };
+
+    let mut problems = Vec::new();
+    for pair in numbered.windows(2) {
+        let ((previous, previous_file), (number, file)) = (pair[0], pair[1]);
+        if number < previous {
+            problems.push(format!(
+                "{} plays before {}; pad the numbers with zeros",
+                previous_file, file
+            ));
+        }
+    }
+
+    let mut numbers: Vec<u32> = numbered.iter().map(|(number, _)| *number).collect();
+    numbers.sort();
+    for pair in numbers.windows(2) {
+        match pair[1] - pair[0] {
+            0 => problems.push(format!("frame number {} is used twice", pair[0])),
+            1 => {}
+            2 => problems.push(format!("frame {} is missing", pair[0] + 1)),
+            _ => problems.push(format!(
+                "frames {} to {} are missing",
+                pair[0] + 1,
+                pair[1] - 1
+            )),
+        }
+    }
+
+    problems
+}
+
+// `walk_07.png` -> 7
+fn frame_number(file: &str) -> Option<u32> {
+    let stem = Path::new(file).file_stem()?.to_str()?;
+    let digits = stem.len() - stem.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    stem[stem.len() - digits..].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn frame_number_reads_trailing_digits() {
+        assert_eq!(frame_number("walk_07.png"), Some(7));
+        assert_eq!(frame_number("Right Animation/40.png"), Some(40));
+        assert_eq!(frame_number("pixelated-0012.png"), Some(12));
+        assert_eq!(frame_number("walk.png"), None);
+        assert_eq!(frame_number("2walk.png"), None);
+    }
+
+    #[test]
+    fn numbering_in_order_has_no_problems() {
+        assert!(numbering_problems(&files(&["01.png", "02.png", "03.png"])).is_empty());
+        // numbers only matter when every file has one
+        assert!(numbering_problems(&files(&["walk.png", "05.png"])).is_empty());
+    }
+
+    #[test]
+    fn numbering_reports_unpadded_numbers() {
+        assert_eq!(
+            numbering_problems(&files(&["10.png", "8.png", "9.png"])),
+            ["10.png plays before 8.png; pad the numbers with zeros"]
+        );
+    }
+
+    #[test]
+    fn numbering_reports_gaps_and_duplicates() {
+        assert_eq!(
+            numbering_problems(&files(&["a1.png", "b1.png", "3.png", "7.png"])),
+            [
+                "frame number 1 is used twice",
+                "frame 2 is missing",
+                "frames 4 to 6 are missing",
+            ]
+        );
+    }
+}
@@ -0,0 +1,8 @@
+// Timing shared by the threads that watch files and sockets for the app
+
+// Editors save in several steps, so changes are gathered until the watched
+// folders have been quiet for this many milliseconds
+pub const SETTLE_MS: u16 = 250;
+
+// How often an idle watcher checks whether anyone still listens
+pub const IDLE_MS: u16 = 1000;